@@ -0,0 +1,57 @@
+//! Compares generating trees in memory, in parallel and as a stream of lines.
+//!
+//! Run with `cargo bench -p fractal_tree --features parallel`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fractal_tree::{tree, visit_tree, TreeParameters};
+
+fn generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generation");
+    group.sample_size(10);
+    let parameters = TreeParameters::default();
+
+    for depth in [12usize, 16usize, 20usize] {
+        group.bench_with_input(BenchmarkId::new("tree", depth), &depth, |b, &depth| {
+            b.iter(|| tree("Hello World", black_box(depth), 100f64, &parameters))
+        });
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(
+            BenchmarkId::new("tree_parallel", depth),
+            &depth,
+            |b, &depth| {
+                b.iter(|| {
+                    fractal_tree::tree_parallel(
+                        "Hello World",
+                        black_box(depth),
+                        100f64,
+                        &parameters,
+                    )
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("visit_tree", depth),
+            &depth,
+            |b, &depth| {
+                b.iter(|| {
+                    let mut length = 0f64;
+                    visit_tree(
+                        "Hello World",
+                        black_box(depth),
+                        100f64,
+                        &parameters,
+                        |_, line| {
+                            length += (line.end.x - line.start.x).hypot(line.end.y - line.start.y)
+                        },
+                    );
+                    length
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, generation);
+criterion_main!(benches);
@@ -0,0 +1,84 @@
+//! Compares the SVG writer with the Handlebars template that was used before.
+//!
+//! Run with `cargo bench -p fractal_tree`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fractal_tree::{render_svg, tree, write_svg, LineSetting, SvgOptions, Tree, TreeParameters};
+use handlebars::{handlebars_helper, Handlebars};
+use serde_json::{json, Value};
+
+/// Template of the former implementation (including its path data bug)
+const SVG_TEMPLATE: &str = r#"
+<svg version="1.1"
+     baseProfile="full"
+     xmlns="http://www.w3.org/2000/svg"
+     xmlns:xlink="http://www.w3.org/1999/xlink"
+     xmlns:ev="http://www.w3.org/2001/xml-events"
+     width="{{format this.bounding_rect.size.width}}" height="{{format this.bounding_rect.size.height}}"
+     viewBox="{{format this.bounding_rect.left_upper.x}} {{format this.bounding_rect.left_upper.y}} {{format this.bounding_rect.size.width}} {{format this.bounding_rect.size.height}} ">
+    <g>
+    {{#each this.lines}}
+        <path d="{{#each lines}}M{{format start.x}},{{format start.y}} L{{format end.x}}{{format end.y}}z{{/each}} " stroke="hsl(90, 100%, {{settings.lightness}}%)" stroke-width="{{format settings.width}}" />
+    {{/each}}
+    </g>
+</svg>"#;
+
+/// Converts the tree into the data the template expects
+fn template_data(tree: &Tree) -> Value {
+    let point = |x: f64, y: f64| json!({ "x": x, "y": y });
+    let max_level = tree.lines.len().saturating_sub(1);
+    let rect = &tree.bounding_rect;
+
+    json!({
+        "lines": tree.lines.iter().enumerate().map(|(level, lines)| {
+            let setting = LineSetting::new(level, max_level);
+            json!({
+                "settings": { "lightness": setting.lightness, "width": setting.width },
+                "lines": lines.iter().map(|l| json!({
+                    "start": point(l.start.x, l.start.y),
+                    "end": point(l.end.x, l.end.y),
+                })).collect::<Vec<_>>(),
+            })
+        }).collect::<Vec<_>>(),
+        "bounding_rect": {
+            "left_upper": point(rect.left_upper.x, rect.left_upper.y),
+            "size": { "width": rect.size.width, "height": rect.size.height },
+        },
+    })
+}
+
+/// Renders like the former implementation, which registered the template on every call
+fn render_template(tree: &Tree) -> String {
+    let mut handlebars = Handlebars::new();
+    handlebars
+        .register_template_string("svg", SVG_TEMPLATE)
+        .unwrap();
+    handlebars_helper!(format: |v: f64| format!("{v:.4}"));
+    handlebars.register_helper("format", Box::new(format));
+
+    handlebars.render("svg", &template_data(tree)).unwrap()
+}
+
+fn svg(c: &mut Criterion) {
+    let mut group = c.benchmark_group("svg");
+    let options = SvgOptions::default();
+
+    for depth in [8usize, 12usize] {
+        let tree = tree("Hello World", depth, 100f64, &TreeParameters::default());
+
+        group.bench_with_input(BenchmarkId::new("template", depth), &tree, |b, tree| {
+            b.iter(|| render_template(black_box(tree)))
+        });
+        group.bench_with_input(BenchmarkId::new("writer", depth), &tree, |b, tree| {
+            b.iter(|| render_svg(black_box(tree), &options))
+        });
+        group.bench_with_input(BenchmarkId::new("writer_sink", depth), &tree, |b, tree| {
+            b.iter(|| write_svg(black_box(tree), &options, std::io::sink()).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, svg);
+criterion_main!(benches);
@@ -0,0 +1,130 @@
+use gif::{Encoder, Frame, Repeat};
+use tiny_skia::Color;
+
+use crate::*;
+
+/// Number of frames per second of animated GIFs
+const FRAMES_PER_SECOND: f64 = 15f64;
+
+/// How long the completely grown tree is shown before the animation restarts (in 1/100 s)
+const FINAL_FRAME_DELAY: u16 = 200u16;
+
+/// Speed of the color quantization (1 = best quality, 30 = fastest)
+const QUANTIZATION_SPEED: i32 = 10i32;
+
+/// Number of frames of an animated GIF, including the completely grown tree.
+///
+/// Rendering time and memory grow with the number of frames times the number
+/// of pixels, hosts may use this to limit the size of GIFs they render.
+pub fn gif_frame_count(animation: &AnimationOptions) -> usize {
+    (animation.duration.max(0f64) * FRAMES_PER_SECOND).ceil() as usize + 1
+}
+
+/// Rasterizes the growth of a tree into an animated GIF.
+///
+/// Frames look like [`render_png`], but with a white background because
+/// GIFs cannot contain semi-transparent pixels. The animation loops forever.
+///
+/// # Arguments
+///
+/// * `tree` - The tree to render.
+/// * `width` - Width of the image in pixels.
+/// * `height` - Height of the image in pixels.
+/// * `palette` - Colors of the branches, green shades if `None`.
+/// * `animation` - Duration and easing of the animation.
+pub fn render_gif(
+    tree: &Tree,
+    width: u32,
+    height: u32,
+    palette: Option<&Palette>,
+    animation: &AnimationOptions,
+) -> Result<Vec<u8>, RasterError> {
+    let invalid_size = || RasterError::InvalidSize { width, height };
+    let gif_width = u16::try_from(width).map_err(|_| invalid_size())?;
+    let gif_height = u16::try_from(height).map_err(|_| invalid_size())?;
+    let encoding_error = |e: gif::EncodingError| RasterError::Encoding(e.to_string());
+
+    let mut encoder =
+        Encoder::new(Vec::new(), gif_width, gif_height, &[]).map_err(encoding_error)?;
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(encoding_error)?;
+
+    let levels = tree.lines.len();
+    let frames = gif_frame_count(animation) - 1;
+    let delay = (100f64 / FRAMES_PER_SECOND).round() as u16;
+    for frame in 0..=frames {
+        let time = frame as f64 / FRAMES_PER_SECOND;
+        let pixmap = rasterize(tree, width, height, Some(Color::WHITE), palette, |level| {
+            animation.level_progress(level, levels, time)
+        })?;
+
+        // The background is opaque, so premultiplied and straight alpha are the same
+        let mut pixels = pixmap.take();
+        let mut gif_frame =
+            Frame::from_rgba_speed(gif_width, gif_height, &mut pixels, QUANTIZATION_SPEED);
+        gif_frame.delay = if frame == frames {
+            FINAL_FRAME_DELAY
+        } else {
+            delay
+        };
+        encoder.write_frame(&gif_frame).map_err(encoding_error)?;
+    }
+
+    encoder
+        .into_inner()
+        .map_err(|e| RasterError::Encoding(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number of frames and size of a GIF
+    fn decode(gif: &[u8]) -> (usize, u16, u16) {
+        let mut decoder = gif::DecodeOptions::new().read_info(gif).unwrap();
+        let mut frames = 0usize;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+
+        (frames, decoder.width(), decoder.height())
+    }
+
+    #[test]
+    fn frame_count_depends_on_duration() {
+        let frames = |duration: f64| {
+            gif_frame_count(&AnimationOptions {
+                duration,
+                ..Default::default()
+            })
+        };
+        assert_eq!(frames(0f64), 1);
+        assert_eq!(frames(-1f64), 1);
+        assert_eq!(frames(1f64), 16);
+        assert_eq!(frames(0.1f64), 3);
+    }
+
+    #[test]
+    fn gif_has_every_frame() {
+        let tree = tree("gif", 5, 100f64, &TreeParameters::default());
+        let animation = AnimationOptions {
+            duration: 0.5f64,
+            ..Default::default()
+        };
+        let gif = render_gif(&tree, 40, 30, None, &animation).unwrap();
+        assert_eq!(decode(&gif), (gif_frame_count(&animation), 40, 30));
+    }
+
+    #[test]
+    fn sizes_must_fit_into_gifs() {
+        let tree = tree("gif", 3, 100f64, &TreeParameters::default());
+        let animation = AnimationOptions::default();
+        for (width, height) in [(70_000u32, 10u32), (10u32, 65_536u32)] {
+            assert!(matches!(
+                render_gif(&tree, width, height, None, &animation),
+                Err(RasterError::InvalidSize { .. })
+            ));
+        }
+    }
+}
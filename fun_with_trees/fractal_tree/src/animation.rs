@@ -0,0 +1,183 @@
+use std::{fmt, str::FromStr};
+
+/// Progression of the growth of a level over time
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ];
+
+    /// Name used by [`FromStr`] and [`fmt::Display`], same as in CSS
+    pub fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease-in",
+            Easing::EaseOut => "ease-out",
+            Easing::EaseInOut => "ease-in-out",
+        }
+    }
+
+    /// Applies the easing to a progress between 0 and 1 (quadratic curves)
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0f64, 1f64);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1f64 - (1f64 - t) * (1f64 - t),
+            Easing::EaseInOut if t < 0.5f64 => 2f64 * t * t,
+            Easing::EaseInOut => 1f64 - 2f64 * (1f64 - t) * (1f64 - t),
+        }
+    }
+
+    /// CSS timing function matching [`Easing::apply`]
+    pub fn css_timing_function(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "cubic-bezier(0.11, 0, 0.5, 0)",
+            Easing::EaseOut => "cubic-bezier(0.5, 1, 0.89, 1)",
+            Easing::EaseInOut => "cubic-bezier(0.45, 0, 0.55, 1)",
+        }
+    }
+}
+
+impl fmt::Display for Easing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Easing::ALL
+            .into_iter()
+            .find(|e| e.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Easing::ALL.iter().map(|e| e.name()).collect();
+                format!(
+                    "unknown easing '{name}', must be one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Settings for animating the growth of a tree.
+///
+/// The levels of the tree are drawn one after another, every level takes
+/// the same share of the duration.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationOptions {
+    /// Total duration of the animation in seconds
+    pub duration: f64,
+
+    /// Progression of the growth of every level
+    pub easing: Easing,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            duration: 3f64,
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl AnimationOptions {
+    /// Duration of the growth of one level in seconds
+    pub fn level_duration(&self, levels: usize) -> f64 {
+        self.duration / levels.max(1) as f64
+    }
+
+    /// How far a level has grown at a point in time, between 0 and 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The level (0 = trunk).
+    /// * `levels` - The total number of levels in the tree.
+    /// * `time` - Seconds since the start of the animation.
+    pub fn level_progress(&self, level: usize, levels: usize, time: f64) -> f64 {
+        let level_duration = self.level_duration(levels);
+        if level_duration <= 0f64 {
+            return 1f64;
+        }
+
+        self.easing
+            .apply((time - level as f64 * level_duration) / level_duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for easing in Easing::ALL {
+            assert_eq!(easing.apply(0f64), 0f64, "{easing}");
+            assert_eq!(easing.apply(1f64), 1f64, "{easing}");
+            assert_eq!(easing.apply(-1f64), 0f64, "{easing}");
+            assert_eq!(easing.apply(2f64), 1f64, "{easing}");
+        }
+
+        assert_eq!(Easing::Linear.apply(0.25f64), 0.25f64);
+        assert_eq!(Easing::EaseIn.apply(0.5f64), 0.25f64);
+        assert_eq!(Easing::EaseOut.apply(0.5f64), 0.75f64);
+        assert_eq!(Easing::EaseInOut.apply(0.25f64), 0.125f64);
+        assert_eq!(Easing::EaseInOut.apply(0.75f64), 0.875f64);
+    }
+
+    #[test]
+    fn easings_are_parsed_by_name() {
+        for easing in Easing::ALL {
+            assert_eq!(easing.name().parse(), Ok(easing));
+        }
+        assert_eq!("EASE-IN".parse(), Ok(Easing::EaseIn));
+        assert_eq!(
+            "bounce".parse::<Easing>(),
+            Err(
+                "unknown easing 'bounce', must be one of linear, ease-in, ease-out, ease-in-out"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn levels_grow_one_after_another() {
+        let animation = AnimationOptions {
+            duration: 4f64,
+            easing: Easing::Linear,
+        };
+        assert_eq!(animation.level_duration(4), 1f64);
+        assert_eq!(animation.level_progress(0, 4, 0.5f64), 0.5f64);
+        assert_eq!(animation.level_progress(1, 4, 0.5f64), 0f64);
+        assert_eq!(animation.level_progress(1, 4, 1.5f64), 0.5f64);
+        assert_eq!(animation.level_progress(3, 4, 4f64), 1f64);
+        assert_eq!(animation.level_progress(0, 4, 4f64), 1f64);
+    }
+
+    #[test]
+    fn without_duration_all_levels_are_grown() {
+        let animation = AnimationOptions {
+            duration: 0f64,
+            ..Default::default()
+        };
+        for level in 0usize..4usize {
+            assert_eq!(animation.level_progress(level, 4, 0f64), 1f64);
+        }
+        assert_eq!(animation.level_progress(0, 0, 0f64), 1f64);
+    }
+}
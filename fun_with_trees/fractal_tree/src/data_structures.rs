@@ -1,13 +1,9 @@
 use std::ops::{Add, AddAssign};
-use serde::Serialize;
+
+use crate::Leaf;
 
 /// Implements a vector in 2D space.
-/// 
-/// The struct implements `serde::Serialize` if the `svg` feature is enabled.
-/// This is necessary because the *handlebars* crate is used to generate
-/// the SVG output and the trait is required for it.
-#[derive(Debug, Default, Clone, Copy)]
-#[cfg_attr(feature = "svg", derive(Serialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Vector2d {
     pub x: f64,
     pub y: f64,
@@ -26,36 +22,21 @@ impl Add for Vector2d {
 }
 
 /// Implements a line in 2D space from a starting point to an endpoint.
-/// 
-/// The struct implements `serde::Serialize` if the `svg` feature is enabled.
-/// This is necessary because the *handlebars* crate is used to generate
-/// the SVG output and the trait is required for it.
-#[derive(Debug, Default, Copy, Clone)]
-#[cfg_attr(feature = "svg", derive(Serialize))]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct Line {
     pub start: Vector2d,
     pub end: Vector2d,
 }
 
 /// Implements a size in 2D space.
-/// 
-/// The struct implements `serde::Serialize` if the `svg` feature is enabled.
-/// This is necessary because the *handlebars* crate is used to generate
-/// the SVG output and the trait is required for it.
 #[derive(Debug, Default, Copy, Clone)]
-#[cfg_attr(feature = "svg", derive(Serialize))]
 pub struct Size {
     pub width: f64,
     pub height: f64,
 }
 
 /// Implements a rectangle consisting of left upper corner, right lower corner, width, and height.
-/// 
-/// The struct implements `serde::Serialize` if the `svg` feature is enabled.
-/// This is necessary because the *handlebars* crate is used to generate
-/// the SVG output and the trait is required for it.
 #[derive(Debug, Default, Copy, Clone)]
-#[cfg_attr(feature = "svg", derive(Serialize))]
 pub struct Rect {
     pub left_upper: Vector2d,
     pub right_lower: Vector2d,
@@ -78,11 +59,66 @@ impl AddAssign<&Line> for Rect {
     }
 }
 
+impl AddAssign<&Rect> for Rect {
+    /// Enlarges rectangle so that it contains the given rectangle.
+    fn add_assign(&mut self, r: &Rect) {
+        *self += &Line { start: r.left_upper, end: r.right_lower };
+    }
+}
+
+/// Represents settings for a line in the tree image.
+#[derive(Debug)]
+pub struct LineSetting {
+    /// Lightness percentage (HSL color mode)
+    pub lightness: u8,
+
+    /// Width of the line
+    pub width: f64,
+}
+
+impl LineSetting {
+    /// Creates line settings from a given tree branching level.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `level` - The current level.
+    /// * `max_level` - The total number of levels in the tree.
+    pub fn new(level: usize, max_level: usize) -> Self {
+        const LEVEL_TO_LIGHTNESS_FACTOR: u8 = 3u8;
+        const LEVEL_TO_WEIGHT_FACTOR: f64 = 3f64;
+
+        LineSetting {
+            lightness: level as u8 * LEVEL_TO_LIGHTNESS_FACTOR,
+            width: (max_level + 1 - level) as f64 * LEVEL_TO_WEIGHT_FACTOR / 2f64,
+        }
+    }
+}
+
 /// Implements a fractal tree.
 /// 
 /// For every level, `lines` contains one entry. The branches of the level
-/// in the nested vector.
+/// in the nested vector. `leaves` is empty unless foliage has been grown
+/// (see [`Tree::grow_foliage`]).
 pub struct Tree {
     pub lines: Vec<Vec<Line>>,
+    pub leaves: Vec<Leaf>,
     pub bounding_rect: Rect,
-}
\ No newline at end of file
+}
+
+impl From<Vec<Vec<Line>>> for Tree {
+    /// Creates a tree from its lines and calculates the bounding rectangle.
+    fn from(lines: Vec<Vec<Line>>) -> Self {
+        let mut bounding_rect: Rect = Default::default();
+        lines.iter().for_each(|lines| {
+            lines.iter().for_each(|line| {
+                bounding_rect += line;
+            });
+        });
+
+        Tree {
+            lines,
+            leaves: Vec::new(),
+            bounding_rect,
+        }
+    }
+}
@@ -1,120 +1,241 @@
+use std::collections::TryReserveError;
+
 use rand::Rng;
-use rand_core::RngCore;
 use rand_pcg::Pcg64;
 use rand_seeder::Seeder;
 
+#[cfg(feature = "gif")]
+mod animated_gif;
+mod animation;
 mod data_structures;
+pub mod lsystem;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod parameters;
+#[cfg(feature = "png")]
+mod png;
+mod scene;
 #[cfg(feature = "svg")]
 mod svg;
+mod theme;
+pub mod tree3d;
 
+#[cfg(feature = "gif")]
+pub use animated_gif::*;
+pub use animation::*;
 pub use data_structures::*;
+#[cfg(feature = "parallel")]
+pub use parallel::*;
+pub use parameters::*;
+#[cfg(feature = "png")]
+pub use png::*;
+pub use scene::*;
 #[cfg(feature = "svg")]
 pub use svg::*;
+pub use theme::*;
 
-/// Generates a branch (=line) for the fractal tree.
-/// 
-/// # Arguments:
-/// 
-/// * `lines` - The lines representing the branches of the tree.
-/// * `start` - Starting point of the branch.
-/// * `angle` - Angle of the line representing the branch.
-/// * `branch_length` - Length of the line representing the branch.
-/// * `level` - Level of the branch (0 = trunk, 1 = first branch, etc.).
-/// * `rng` - Random number generator.
-fn branch(
-    lines: &mut [Vec<Line>],
+/// A branch that still has to be drawn, see [`grow`].
+#[derive(Debug, Clone, Copy)]
+struct Branch {
     start: Vector2d,
     angle: f64,
-    mut branch_length: f64,
-    mut level: usize,
-    rng: &mut impl RngCore,
+    length: f64,
+    level: usize,
+}
+
+impl Branch {
+    /// The trunk of a tree.
+    fn trunk(trunk_length: f64) -> Self {
+        Branch {
+            start: Default::default(),
+            angle: -90f64.to_radians(),
+            length: trunk_length,
+            level: 0,
+        }
+    }
+}
+
+/// Number of random numbers [`grow`] draws for a branch and all branches
+/// growing out of it.
+///
+/// Every branch that is not a tip draws exactly two numbers. The random
+/// number generator has a period of 2^128, so wrapping arithmetic results
+/// in the correct number of steps even for trees too large to generate.
+///
+/// # Arguments
+///
+/// * `levels` - Number of levels of the subtree (1 = just the branch itself).
+/// * `branches` - Number of branches growing out of every node.
+fn random_numbers_in_subtree(levels: usize, branches: usize) -> u128 {
+    let mut nodes = 0u128;
+    let mut nodes_of_level = 1u128;
+    for _ in 1..levels {
+        nodes = nodes.wrapping_add(nodes_of_level);
+        nodes_of_level = nodes_of_level.wrapping_mul(branches as u128);
+    }
+
+    nodes.wrapping_mul(2)
+}
+
+/// Generates the branches (=lines) of the fractal tree depth-first without
+/// recursion.
+///
+/// Branches are visited in the same order as a recursive implementation
+/// would, the random numbers are drawn in the same order, too.
+///
+/// # Arguments:
+///
+/// * `root` - The first branch (e.g. the trunk).
+/// * `levels` - Number of levels of the whole tree.
+/// * `parameters` - Parameters influencing the visual appearance of the tree.
+/// * `rng` - Random number generator.
+/// * `cut_level` - Branches of this level are not generated. Instead, they are
+///   passed to `cut` together with the state of the random number generator
+///   at which their generation would start.
+/// * `visit` - Receives the level and the line of every generated branch.
+/// * `cut` - Receives the branches of `cut_level`.
+fn grow(
+    root: Branch,
+    levels: usize,
+    parameters: &TreeParameters,
+    rng: &mut Pcg64,
+    cut_level: usize,
+    mut visit: impl FnMut(usize, Line),
+    mut cut: impl FnMut(Branch, &Pcg64),
 ) {
-    // Some parameter influencing the visual appearance of the tree
-    const BRANCH_SHORTENING_FACTOR: f64 = 0.8f64;
-    const RANDOM_ANGLE_CHANGE_FACTOR: f64 = 35f64;
-    const RANDOM_BRANCH_LENGTH_CHANGE_FACTOR: f64 = 25f64;
-    const ANGLE_CHANGE: f64 = 20f64 * std::f64::consts::PI / 180f64;
-
-    debug_assert!(level < lines.len(), "level is out of boundes, must be lower than {}", lines.len());
-
-    // Calculate the end point of the branch
-    let end = start
-        + Vector2d {
-            x: angle.cos() * branch_length,
-            y: angle.sin() * branch_length,
-        };
-
-    // Add the line representing the branch to the lines
-    lines[level].push(Line { start, end });
-
-    // If we did not yet reach the maximum level, create a new branch
-    level += 1;
-    if level < lines.len() {
-        // Shorten the branch
-        branch_length *= BRANCH_SHORTENING_FACTOR;
-
-        // Add a little bit of randomness to the branch's angle and length
-        let angle_change =
-            (rng.gen_range(-0.5f64..0.5f64) * RANDOM_ANGLE_CHANGE_FACTOR).to_radians();
-        let branch_length_change =
-            rng.gen_range(-0.5f64..0.5f64) * RANDOM_BRANCH_LENGTH_CHANGE_FACTOR;
-
-        // Generate two new branches recursively
-        branch(
-            lines,
-            end,
-            angle - ANGLE_CHANGE + angle_change,
-            branch_length + branch_length_change,
-            level,
-            rng,
-        );
-        branch(
-            lines,
-            end,
-            angle + ANGLE_CHANGE + angle_change,
-            branch_length + branch_length_change,
-            level,
-            rng,
+    let branch_angles: Vec<f64> = parameters.branch_angles().collect();
+
+    // Depth-first, so the stack never contains more than the siblings of
+    // the branches on the way from the root to the current branch
+    let mut stack = Vec::with_capacity(levels.saturating_sub(root.level) * branch_angles.len());
+    stack.push(root);
+
+    while let Some(branch) = stack.pop() {
+        if branch.level >= levels {
+            continue;
+        }
+        if branch.level >= cut_level {
+            cut(branch, rng);
+            rng.advance(random_numbers_in_subtree(
+                levels - branch.level,
+                branch_angles.len(),
+            ));
+            continue;
+        }
+
+        // Calculate the end point of the branch
+        let end = branch.start
+            + Vector2d {
+                x: branch.angle.cos() * branch.length,
+                y: branch.angle.sin() * branch.length,
+            };
+        visit(
+            branch.level,
+            Line {
+                start: branch.start,
+                end,
+            },
         );
+
+        // If we did not yet reach the maximum level, create new branches
+        let level = branch.level + 1;
+        if level < levels {
+            // Shorten the branch
+            let length = branch.length * parameters.shortening;
+
+            // Add a little bit of randomness to the branch's angle and length
+            let angle_change =
+                (rng.gen_range(-0.5f64..0.5f64) * parameters.angle_jitter).to_radians();
+            let length_change = rng.gen_range(-0.5f64..0.5f64) * parameters.length_jitter;
+
+            // Push in reverse order so that the first branch is generated first
+            for branch_angle in branch_angles.iter().rev() {
+                stack.push(Branch {
+                    start: end,
+                    angle: parameters.bend(branch.angle + branch_angle + angle_change),
+                    length: length + length_change,
+                    level,
+                });
+            }
+        }
     }
 }
 
-/// Generates a fractal tree.
-/// 
+/// Allocates the lines of a tree. It contains the necessary number of vectors
+/// (one for each level of the tree) with enough capacity for all branches.
+///
+/// Fails instead of aborting if the memory cannot be reserved.
+///
+/// # Arguments
+///
+/// * `levels` - Levels of the tree to allocate.
+/// * `branches` - Number of branches growing out of every node.
+fn allocate_lines(levels: usize, branches: usize) -> Result<Vec<Vec<Line>>, TryReserveError> {
+    let mut lines = Vec::new();
+    lines.try_reserve_exact(levels)?;
+    let mut capacity = 1usize;
+    for _ in 0..levels {
+        let mut level = Vec::new();
+        level.try_reserve_exact(capacity)?;
+        lines.push(level);
+
+        // Saturated capacities cannot be reserved, so they fail the next level
+        capacity = capacity.saturating_mul(branches);
+    }
+
+    Ok(lines)
+}
+
+/// Generates a fractal tree without storing it.
+///
+/// The branches are passed to `visit` (level and line) in the same order as
+/// they appear in [`tree`], so very deep trees can be written or analyzed
+/// using constant memory.
+///
 /// # Arguments:
-/// 
+///
 /// * `seed` - Seed for the random number generator.
 /// * `levels` - Number of levels of the tree.
 /// * `trunk_length` - Length of the trunk of the tree.
-pub fn tree<S: AsRef<str>>(seed: S, levels: usize, trunk_length: f64) -> Tree {
+/// * `parameters` - Parameters influencing the visual appearance of the tree.
+/// * `visit` - Receives every branch of the tree.
+pub fn visit_tree<S: AsRef<str>>(
+    seed: S,
+    levels: usize,
+    trunk_length: f64,
+    parameters: &TreeParameters,
+    visit: impl FnMut(usize, Line),
+) {
     // Generate a seeded random number generator
     let mut rng: Pcg64 = Seeder::from(seed.as_ref()).make_rng();
 
-    // Allocate the tree data structure. It fills `lines` with 
-    // the necessary number of vectors (one for each level of the tree).
-    let mut tree = Tree {
-        lines: (0..levels).into_iter().map(|_| Vec::new()).collect(),
-        bounding_rect: Default::default(),
-    };
-
-    // Generate the trunk of the tree
-    branch(
-        &mut tree.lines,
-        Default::default(),
-        -90f64.to_radians(),
-        trunk_length,
-        0,
+    grow(
+        Branch::trunk(trunk_length),
+        levels,
+        parameters,
         &mut rng,
+        usize::MAX,
+        visit,
+        |_, _| {},
     );
+}
 
-    // Calculate the bounding rectangle of the tree
-    let mut bounding_rect: Rect = Default::default();
-    tree.lines.iter().for_each(|lines| {
-        lines.iter().for_each(|line| {
-            bounding_rect += line;
-        });
+/// Generates a fractal tree.
+/// 
+/// # Arguments:
+/// 
+/// * `seed` - Seed for the random number generator.
+/// * `levels` - Number of levels of the tree.
+/// * `trunk_length` - Length of the trunk of the tree.
+/// * `parameters` - Parameters influencing the visual appearance of the tree.
+pub fn tree<S: AsRef<str>>(seed: S, levels: usize, trunk_length: f64, parameters: &TreeParameters) -> Tree {
+    // Trees too large to preallocate grow line by line (as far as memory goes)
+    let mut lines = allocate_lines(levels, parameters.branch_angles().count())
+        .unwrap_or_else(|_| vec![Vec::new(); levels]);
+    visit_tree(seed, levels, trunk_length, parameters, |level, line| {
+        lines[level].push(line)
     });
-    tree.bounding_rect = bounding_rect;
 
-    tree
+    // Calculate the bounding rectangle of the tree
+    Tree::from(lines)
 }
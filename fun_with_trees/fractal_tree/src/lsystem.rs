@@ -0,0 +1,556 @@
+//! Lindenmayer systems (L-systems).
+//!
+//! An L-system consists of an axiom and production rules. Every iteration
+//! replaces each symbol of the current string with the successor of its rule.
+//! The resulting string is interpreted by a turtle that draws the existing
+//! [`Line`] data structures, so L-systems can be rendered like the fractal tree.
+//!
+//! Rules can be
+//!
+//! * deterministic (`F` → `F+F--F+F`),
+//! * stochastic (several rules for the same symbol, one is chosen randomly
+//!   according to their weights), and
+//! * parametric (symbols carry parameters like `F(10)`, the successor is
+//!   calculated from them and the rule may have a condition).
+//!
+//! The turtle understands the following symbols, all others are ignored:
+//!
+//! | Symbol | Meaning                                                    |
+//! |--------|------------------------------------------------------------|
+//! | `F`, `G` | Move forward and draw a line                             |
+//! | `f`    | Move forward without drawing                               |
+//! | `+`    | Turn left                                                  |
+//! | `-`    | Turn right                                                 |
+//! | `\|`   | Turn around                                                |
+//! | `[`    | Push the turtle's state (starts a branch)                  |
+//! | `]`    | Pop the turtle's state (ends a branch)                     |
+//!
+//! The first parameter of `F`, `G`, and `f` overrides the step length, the
+//! first parameter of `+` and `-` overrides the angle (in degrees).
+
+use std::{fmt, str::FromStr};
+
+use rand::Rng;
+use rand_core::RngCore;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+
+use crate::{Line, Tree, Vector2d};
+
+/// Largest number of modules [`LSystem::expand`] creates, the number of
+/// modules grows exponentially with the number of iterations
+pub const MAX_MODULES: usize = 1usize << 20;
+
+/// A symbol with optional parameters, e.g. `F(10)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub symbol: char,
+    pub parameters: Vec<f64>,
+}
+
+impl Module {
+    pub fn new(symbol: char) -> Self {
+        Module {
+            symbol,
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn with_parameters(symbol: char, parameters: &[f64]) -> Self {
+        Module {
+            symbol,
+            parameters: parameters.to_vec(),
+        }
+    }
+}
+
+/// Error when parsing a string of modules
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// A `(` is not closed
+    UnclosedParameters { position: usize },
+    /// A parameter is not a number
+    InvalidParameter { position: usize, parameter: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnclosedParameters { position } => {
+                write!(f, "parameter list at position {position} is not closed")
+            }
+            ParseError::InvalidParameter {
+                position,
+                parameter,
+            } => write!(
+                f,
+                "parameter '{parameter}' at position {position} is not a number"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error when expanding an L-system
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpandError {
+    /// The iteration results in more than [`MAX_MODULES`] modules
+    TooManyModules { iteration: usize },
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpandError::TooManyModules { iteration } => write!(
+                f,
+                "iteration {iteration} results in more than {MAX_MODULES} modules"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExpandError {}
+
+/// Parses a string like `F(10)+[X]` into modules.
+pub fn parse_modules(text: &str) -> Result<Vec<Module>, ParseError> {
+    let mut modules: Vec<Module> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((_, symbol)) = chars.next() {
+        if symbol.is_whitespace() {
+            continue;
+        }
+
+        let mut module = Module::new(symbol);
+        if let Some((position, '(')) = chars.peek().copied() {
+            chars.next();
+            let mut list = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, ')')) => break,
+                    Some((_, c)) => list.push(c),
+                    None => return Err(ParseError::UnclosedParameters { position }),
+                }
+            }
+
+            for parameter in list.split(',').map(str::trim) {
+                module.parameters.push(parameter.parse().map_err(|_| {
+                    ParseError::InvalidParameter {
+                        position,
+                        parameter: parameter.to_string(),
+                    }
+                })?);
+            }
+        }
+
+        modules.push(module);
+    }
+
+    Ok(modules)
+}
+
+/// Calculates the successor of a parametric module from its parameters
+pub type ParametricProduction = fn(&[f64]) -> Vec<Module>;
+
+/// Decides whether a parametric rule applies to a module
+pub type Condition = fn(&[f64]) -> bool;
+
+/// Successor of a [`Rule`]
+#[derive(Clone)]
+pub enum Production {
+    /// Replaces the symbol with fixed modules
+    Modules(Vec<Module>),
+    /// Calculates the successor from the parameters of the symbol
+    Parametric(ParametricProduction),
+}
+
+/// Production rule replacing a symbol
+#[derive(Clone)]
+pub struct Rule {
+    pub symbol: char,
+    /// Rules without condition always apply
+    pub condition: Option<Condition>,
+    /// Relative probability if several rules apply to a symbol
+    pub weight: f64,
+    pub production: Production,
+}
+
+impl Rule {
+    /// Checks whether the rule applies to a module
+    fn applies_to(&self, module: &Module) -> bool {
+        self.symbol == module.symbol
+            && self
+                .condition
+                .is_none_or(|condition| condition(&module.parameters))
+    }
+
+    /// Calculates the successor of a module
+    fn apply(&self, module: &Module) -> Vec<Module> {
+        match &self.production {
+            Production::Modules(modules) => modules.clone(),
+            Production::Parametric(production) => production(&module.parameters),
+        }
+    }
+}
+
+/// An L-system with its turtle settings
+#[derive(Clone)]
+pub struct LSystem {
+    pub axiom: Vec<Module>,
+    pub rules: Vec<Rule>,
+    /// Angle for `+` and `-` in degrees
+    pub angle: f64,
+    /// Length of lines drawn by `F` and `G`
+    pub step: f64,
+    /// Initial direction of the turtle in degrees (-90 = up)
+    pub heading: f64,
+}
+
+impl LSystem {
+    /// Creates an L-system without rules.
+    ///
+    /// # Arguments:
+    ///
+    /// * `axiom` - Initial string, e.g. `F--F--F`.
+    /// * `angle` - Angle for `+` and `-` in degrees.
+    pub fn new(axiom: &str, angle: f64) -> Result<Self, ParseError> {
+        Ok(LSystem {
+            axiom: parse_modules(axiom)?,
+            rules: Vec::new(),
+            angle,
+            step: 10f64,
+            heading: 0f64,
+        })
+    }
+
+    /// Sets the length of lines drawn by `F` and `G`
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Sets the initial direction of the turtle in degrees (-90 = up)
+    pub fn with_heading(mut self, heading: f64) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    /// Adds a deterministic rule replacing `symbol` with `successor`
+    pub fn with_rule(self, symbol: char, successor: &str) -> Result<Self, ParseError> {
+        self.with_stochastic_rule(symbol, 1f64, successor)
+    }
+
+    /// Adds a rule that is chosen with a probability relative to `weight`
+    /// among all rules for the same symbol
+    pub fn with_stochastic_rule(
+        mut self,
+        symbol: char,
+        weight: f64,
+        successor: &str,
+    ) -> Result<Self, ParseError> {
+        self.rules.push(Rule {
+            symbol,
+            condition: None,
+            weight,
+            production: Production::Modules(parse_modules(successor)?),
+        });
+        Ok(self)
+    }
+
+    /// Adds a rule calculating the successor from the symbol's parameters.
+    /// The rule only applies if `condition` is `None` or returns `true`.
+    pub fn with_parametric_rule(
+        mut self,
+        symbol: char,
+        condition: Option<Condition>,
+        production: ParametricProduction,
+    ) -> Self {
+        self.rules.push(Rule {
+            symbol,
+            condition,
+            weight: 1f64,
+            production: Production::Parametric(production),
+        });
+        self
+    }
+
+    /// Applies the rules `iterations` times to the axiom.
+    ///
+    /// Symbols without applicable rule are kept. If several rules apply,
+    /// `rng` chooses one according to their weights. Fails as soon as the
+    /// result gets longer than [`MAX_MODULES`].
+    pub fn expand(
+        &self,
+        iterations: usize,
+        rng: &mut impl RngCore,
+    ) -> Result<Vec<Module>, ExpandError> {
+        let mut modules = self.axiom.clone();
+        for iteration in 1..=iterations {
+            let mut next = Vec::with_capacity(modules.len() * 2);
+            for module in modules.iter() {
+                match self.choose_rule(module, rng) {
+                    Some(rule) => next.extend(rule.apply(module)),
+                    None => next.push(module.clone()),
+                }
+                if next.len() > MAX_MODULES {
+                    return Err(ExpandError::TooManyModules { iteration });
+                }
+            }
+            modules = next;
+        }
+
+        Ok(modules)
+    }
+
+    /// Picks one of the rules applying to `module`
+    fn choose_rule(&self, module: &Module, rng: &mut impl RngCore) -> Option<&Rule> {
+        let rules: Vec<&Rule> = self.rules.iter().filter(|r| r.applies_to(module)).collect();
+        let total: f64 = rules.iter().map(|r| r.weight).sum();
+        if rules.len() <= 1 || total <= 0f64 {
+            return rules.first().copied();
+        }
+
+        // Only draw random numbers if there is a choice, so that
+        // deterministic systems do not depend on the random number generator
+        let mut choice = rng.gen_range(0f64..total);
+        for rule in rules.iter() {
+            if choice < rule.weight {
+                return Some(rule);
+            }
+            choice -= rule.weight;
+        }
+        rules.last().copied()
+    }
+
+    /// Draws the modules with a turtle.
+    ///
+    /// Lines are grouped by their branch depth (number of open `[`), so that
+    /// the SVG output renders branches like the levels of the fractal tree.
+    pub fn interpret(&self, modules: &[Module]) -> Tree {
+        #[derive(Clone, Copy)]
+        struct Turtle {
+            position: Vector2d,
+            angle: f64,
+        }
+
+        let mut turtle = Turtle {
+            position: Default::default(),
+            angle: self.heading.to_radians(),
+        };
+        let mut stack = Vec::new();
+        let mut lines: Vec<Vec<Line>> = vec![Vec::new()];
+
+        for module in modules.iter() {
+            let parameter = module.parameters.first().copied();
+            match module.symbol {
+                'F' | 'G' | 'f' => {
+                    let length = parameter.unwrap_or(self.step);
+                    let end = turtle.position
+                        + Vector2d {
+                            x: turtle.angle.cos() * length,
+                            y: turtle.angle.sin() * length,
+                        };
+                    if module.symbol != 'f' {
+                        if lines.len() <= stack.len() {
+                            lines.resize(stack.len() + 1, Vec::new());
+                        }
+                        lines[stack.len()].push(Line {
+                            start: turtle.position,
+                            end,
+                        });
+                    }
+                    turtle.position = end;
+                }
+                // y grows downwards in the generated images, so left turns are negative
+                '+' => turtle.angle -= parameter.unwrap_or(self.angle).to_radians(),
+                '-' => turtle.angle += parameter.unwrap_or(self.angle).to_radians(),
+                '|' => turtle.angle += std::f64::consts::PI,
+                '[' => stack.push(turtle),
+                ']' => turtle = stack.pop().unwrap_or(turtle),
+                _ => {}
+            }
+        }
+
+        Tree::from(lines)
+    }
+
+    /// Expands the L-system and draws the result.
+    ///
+    /// # Arguments:
+    ///
+    /// * `seed` - Seed for the random number generator used by stochastic rules.
+    /// * `iterations` - Number of times the rules are applied.
+    pub fn tree<S: AsRef<str>>(&self, seed: S, iterations: usize) -> Result<Tree, ExpandError> {
+        let mut rng: Pcg64 = Seeder::from(seed.as_ref()).make_rng();
+        Ok(self.interpret(&self.expand(iterations, &mut rng)?))
+    }
+}
+
+/// Well-known L-systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    FractalPlant,
+    KochCurve,
+    SierpinskiTriangle,
+    DragonCurve,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::FractalPlant,
+        Preset::KochCurve,
+        Preset::SierpinskiTriangle,
+        Preset::DragonCurve,
+    ];
+
+    /// Name used by [`FromStr`] and [`fmt::Display`]
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::FractalPlant => "plant",
+            Preset::KochCurve => "koch",
+            Preset::SierpinskiTriangle => "sierpinski",
+            Preset::DragonCurve => "dragon",
+        }
+    }
+
+    /// Number of iterations that gives a detailed, but not too large result
+    pub fn default_iterations(self) -> usize {
+        match self {
+            Preset::FractalPlant => 6,
+            Preset::KochCurve => 4,
+            Preset::SierpinskiTriangle => 6,
+            Preset::DragonCurve => 12,
+        }
+    }
+
+    pub fn lsystem(self) -> LSystem {
+        let system = match self {
+            Preset::FractalPlant => LSystem::new("X", 25f64).and_then(|s| {
+                s.with_heading(-90f64)
+                    .with_step(5f64)
+                    .with_rule('X', "F+[[X]-X]-F[-FX]+X")?
+                    .with_rule('F', "FF")
+            }),
+            Preset::KochCurve => {
+                LSystem::new("F", 60f64).and_then(|s| s.with_rule('F', "F+F--F+F"))
+            }
+            Preset::SierpinskiTriangle => LSystem::new("F-G-G", 120f64)
+                .and_then(|s| s.with_rule('F', "F-G+F+G-F")?.with_rule('G', "GG")),
+            Preset::DragonCurve => LSystem::new("F", 90f64)
+                .and_then(|s| s.with_rule('F', "F+G")?.with_rule('G', "F-G")),
+        };
+
+        system.expect("presets are valid")
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Preset::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown preset '{name}', must be one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_count(tree: &Tree) -> usize {
+        tree.lines.iter().map(Vec::len).sum()
+    }
+
+    #[test]
+    fn koch_curve_replaces_every_line_with_four() {
+        let system = Preset::KochCurve.lsystem();
+        assert_eq!(line_count(&system.tree("", 0).unwrap()), 1);
+        assert_eq!(line_count(&system.tree("", 1).unwrap()), 4);
+        assert_eq!(line_count(&system.tree("", 2).unwrap()), 16);
+    }
+
+    #[test]
+    fn sierpinski_triangle_has_nine_lines_after_one_iteration() {
+        // F-G-G becomes F-G+F+G-F - GG - GG
+        let tree = Preset::SierpinskiTriangle.lsystem().tree("", 1).unwrap();
+        assert_eq!(line_count(&tree), 9);
+    }
+
+    #[test]
+    fn presets_stay_below_the_module_limit() {
+        let mut rng: Pcg64 = Seeder::from("").make_rng();
+        for preset in Preset::ALL {
+            let system = preset.lsystem();
+            assert!(system.expand(preset.default_iterations(), &mut rng).is_ok());
+        }
+    }
+
+    #[test]
+    fn expansion_stops_at_the_module_limit() {
+        let system = LSystem::new("F", 90f64)
+            .unwrap()
+            .with_rule('F', "FF")
+            .unwrap();
+        let mut rng: Pcg64 = Seeder::from("").make_rng();
+        assert_eq!(system.expand(20, &mut rng).unwrap().len(), MAX_MODULES);
+        assert_eq!(
+            system.expand(64, &mut rng),
+            Err(ExpandError::TooManyModules { iteration: 21 })
+        );
+    }
+
+    #[test]
+    fn modules_with_parameters() {
+        assert_eq!(
+            parse_modules("F(10, 2.5)+ [X]").unwrap(),
+            vec![
+                Module::with_parameters('F', &[10f64, 2.5f64]),
+                Module::new('+'),
+                Module::new('['),
+                Module::new('X'),
+                Module::new(']'),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_modules() {
+        assert_eq!(
+            parse_modules("F+F(10"),
+            Err(ParseError::UnclosedParameters { position: 3 })
+        );
+        assert_eq!(
+            parse_modules("F(10,x)"),
+            Err(ParseError::InvalidParameter {
+                position: 1,
+                parameter: "x".to_string()
+            })
+        );
+        assert_eq!(
+            parse_modules("F()"),
+            Err(ParseError::InvalidParameter {
+                position: 1,
+                parameter: String::new()
+            })
+        );
+        assert!(LSystem::new("F", 60f64)
+            .unwrap()
+            .with_rule('F', "F(")
+            .is_err());
+    }
+}
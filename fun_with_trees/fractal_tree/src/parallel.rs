@@ -0,0 +1,162 @@
+use std::fmt;
+
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+use rayon::prelude::*;
+
+use crate::*;
+
+/// Number of subtrees per thread, more subtrees balance the load better
+const SUBTREES_PER_THREAD: usize = 8usize;
+
+/// Errors of [`tree_parallel`]
+#[derive(Debug)]
+pub enum GenerationError {
+    /// The lines of the tree do not fit into memory
+    OutOfMemory { levels: usize },
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerationError::OutOfMemory { levels } => write!(
+                f,
+                "tree with {levels} levels does not fit into memory, reduce levels or branches"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
+/// Generates a fractal tree using all available threads.
+///
+/// The tree is exactly the same as the one generated by [`tree`] for the
+/// same arguments. The upper levels are generated first, then the subtrees
+/// growing out of them are generated in parallel. Every subtree gets its own
+/// copy of the random number generator, advanced to the position at which
+/// [`tree`] would start generating the subtree.
+///
+/// All lines are allocated up front and the subtrees write into their part
+/// of every level, so no more memory than for the tree itself is needed.
+///
+/// # Arguments:
+///
+/// * `seed` - Seed for the random number generator.
+/// * `levels` - Number of levels of the tree.
+/// * `trunk_length` - Length of the trunk of the tree.
+/// * `parameters` - Parameters influencing the visual appearance of the tree.
+pub fn tree_parallel<S: AsRef<str>>(
+    seed: S,
+    levels: usize,
+    trunk_length: f64,
+    parameters: &TreeParameters,
+) -> Result<Tree, GenerationError> {
+    let branches = parameters.branch_angles().count();
+
+    // Find the first level with enough branches to keep all threads busy
+    let wanted_subtrees = rayon::current_num_threads() * SUBTREES_PER_THREAD;
+    let mut cut_level = 0usize;
+    let mut subtrees = 1usize;
+    while subtrees < wanted_subtrees && cut_level + 1 < levels {
+        cut_level += 1;
+        subtrees = subtrees.saturating_mul(branches);
+    }
+    if cut_level == 0 {
+        return Ok(tree(seed, levels, trunk_length, parameters));
+    }
+
+    // Generate the levels above the cut and remember where the subtrees start
+    let mut rng: Pcg64 = Seeder::from(seed.as_ref()).make_rng();
+    let mut lines =
+        allocate_lines(levels, branches).map_err(|_| GenerationError::OutOfMemory { levels })?;
+    let mut roots = Vec::with_capacity(subtrees);
+    grow(
+        Branch::trunk(trunk_length),
+        levels,
+        parameters,
+        &mut rng,
+        cut_level,
+        |level, line| lines[level].push(line),
+        |root, rng| roots.push((root, rng.clone())),
+    );
+
+    // All subtrees have the same number of lines per level and [`tree`] stores
+    // them one after the other, so every subtree gets its own part of each level
+    let mut parts: Vec<Vec<&mut [Line]>> = roots.iter().map(|_| Vec::new()).collect();
+    let mut lines_per_subtree = 1usize;
+    for level in lines[cut_level..].iter_mut() {
+        // Fits into the allocated capacity, the vector is not reallocated
+        level.resize(lines_per_subtree * roots.len(), Line::default());
+        for (part, chunk) in parts.iter_mut().zip(level.chunks_mut(lines_per_subtree)) {
+            part.push(chunk);
+        }
+        lines_per_subtree *= branches;
+    }
+
+    roots
+        .into_par_iter()
+        .zip(parts)
+        .for_each(|((root, mut rng), mut part)| {
+            let mut written = vec![0usize; part.len()];
+            grow(
+                root,
+                levels,
+                parameters,
+                &mut rng,
+                usize::MAX,
+                |level, line| {
+                    let level = level - cut_level;
+                    part[level][written[level]] = line;
+                    written[level] += 1;
+                },
+                |_, _| {},
+            );
+        });
+
+    Ok(Tree::from(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_tree_as_sequential_generation() {
+        // The number of threads decides at which level the tree is cut
+        for threads in [1usize, 3usize, 16usize] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            for branches in 2usize..=4usize {
+                let parameters = TreeParameters {
+                    branches,
+                    ..Default::default()
+                };
+                for levels in [0usize, 1usize, 2usize, 5usize, 8usize] {
+                    let expected = tree("parallel", levels, 100f64, &parameters);
+                    let actual = pool.install(|| {
+                        tree_parallel("parallel", levels, 100f64, &parameters).unwrap()
+                    });
+                    assert_eq!(
+                        actual.lines, expected.lines,
+                        "{threads} threads, {branches} branches, {levels} levels"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trees_too_large_for_memory_fail() {
+        let parameters = TreeParameters {
+            branches: 8,
+            ..Default::default()
+        };
+        assert!(matches!(
+            tree_parallel("parallel", 100, 100f64, &parameters),
+            Err(GenerationError::OutOfMemory { levels: 100 })
+        ));
+    }
+}
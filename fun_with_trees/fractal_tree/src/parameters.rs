@@ -0,0 +1,127 @@
+/// Parameters influencing the visual appearance of the tree.
+///
+/// Angles are in degrees, lengths in the same unit as the trunk length.
+/// The default values generate the classic binary fractal tree.
+#[derive(Debug, Clone, Copy)]
+pub struct TreeParameters {
+    /// Factor by which every level's branches are shorter than their parent
+    pub shortening: f64,
+
+    /// Angle between a branch and its parent
+    pub branch_angle: f64,
+
+    /// Angle of the leftmost branch, overrides `branch_angle`
+    pub left_angle: Option<f64>,
+
+    /// Angle of the rightmost branch, overrides `branch_angle`
+    pub right_angle: Option<f64>,
+
+    /// Maximum random change of the branches' angle (total range, half in each direction)
+    pub angle_jitter: f64,
+
+    /// Maximum random change of the branches' length (total range, half in each direction)
+    pub length_jitter: f64,
+
+    /// Number of branches growing out of every node. Values below 2 are treated as 2.
+    pub branches: usize,
+
+    /// Bends branches downwards (positive) or upwards (negative), e.g. `0.1`
+    pub gravity: f64,
+
+    /// Bends branches to the right (positive) or to the left (negative), e.g. `0.1`
+    pub wind: f64,
+}
+
+impl Default for TreeParameters {
+    fn default() -> Self {
+        Self {
+            shortening: 0.8f64,
+            branch_angle: 20f64,
+            left_angle: None,
+            right_angle: None,
+            angle_jitter: 35f64,
+            length_jitter: 25f64,
+            branches: 2usize,
+            gravity: 0f64,
+            wind: 0f64,
+        }
+    }
+}
+
+impl TreeParameters {
+    /// Angles (in radians) of the branches growing out of a node, relative to
+    /// their parent. Branches are spread evenly from the leftmost to the
+    /// rightmost angle.
+    pub fn branch_angles(&self) -> impl Iterator<Item = f64> {
+        let left = -self.left_angle.unwrap_or(self.branch_angle).to_radians();
+        let right = self.right_angle.unwrap_or(self.branch_angle).to_radians();
+        let branches = self.branches.max(2);
+
+        (0..branches).map(move |i| left + (right - left) * i as f64 / (branches - 1) as f64)
+    }
+
+    /// Number of lines of a tree with the given number of levels, `None` if
+    /// it does not fit into `usize`.
+    pub fn line_count(&self, levels: usize) -> Option<usize> {
+        let branches = self.branches.max(2);
+        let mut lines = 0usize;
+        let mut lines_of_level = 1usize;
+        for _ in 0..levels {
+            lines = lines.checked_add(lines_of_level)?;
+            lines_of_level = lines_of_level.saturating_mul(branches);
+        }
+
+        Some(lines)
+    }
+
+    /// Applies gravity and wind to the direction of a branch.
+    ///
+    /// The bias is added to the branch's unit vector, so it has a larger
+    /// effect on branches pointing against it.
+    pub fn bend(&self, angle: f64) -> f64 {
+        if self.gravity == 0f64 && self.wind == 0f64 {
+            return angle;
+        }
+
+        // y grows downwards in the generated images
+        (angle.sin() + self.gravity).atan2(angle.cos() + self.wind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tree, visit_tree};
+
+    #[test]
+    fn line_count_matches_generated_trees() {
+        for branches in 1usize..=5usize {
+            let parameters = TreeParameters {
+                branches,
+                ..Default::default()
+            };
+            for levels in 0usize..=7usize {
+                let lines: usize = tree("count", levels, 100f64, &parameters)
+                    .lines
+                    .iter()
+                    .map(Vec::len)
+                    .sum();
+                let mut visited = 0usize;
+                visit_tree("count", levels, 100f64, &parameters, |_, _| visited += 1);
+
+                assert_eq!(parameters.line_count(levels), Some(lines));
+                assert_eq!(visited, lines);
+            }
+        }
+    }
+
+    #[test]
+    fn line_count_overflows() {
+        let parameters = TreeParameters {
+            branches: 8,
+            ..Default::default()
+        };
+        assert_eq!(parameters.line_count(21), Some((8usize.pow(21) - 1) / 7));
+        assert_eq!(parameters.line_count(100), None);
+    }
+}
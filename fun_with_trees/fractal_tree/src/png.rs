@@ -0,0 +1,229 @@
+use std::fmt;
+
+use tiny_skia::{Color, FillRule, LineCap, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::*;
+
+/// Hue of all lines (HSL color mode) without palette, same as in the SVG output
+const HUE: f64 = 90f64;
+
+/// Empty space around the tree in pixels
+const PADDING: f64 = 10f64;
+
+/// Largest number of pixels of an image (1 GiB of RGBA data), larger images
+/// would abort on allocation
+const MAX_PIXELS: u64 = 1u64 << 28;
+
+/// Error when rasterizing a tree into a PNG or GIF image
+#[derive(Debug)]
+pub enum RasterError {
+    /// Width or height is zero or too large
+    InvalidSize { width: u32, height: u32 },
+    /// The image could not be encoded
+    Encoding(String),
+}
+
+impl fmt::Display for RasterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RasterError::InvalidSize { width, height } => {
+                write!(f, "cannot create an image with {width}x{height} pixels")
+            }
+            RasterError::Encoding(message) => write!(f, "image encoding failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RasterError {}
+
+/// Converts a color from HSL to RGB.
+///
+/// # Arguments
+///
+/// * `color` - The HSL color.
+/// * `alpha` - Opacity between 0 and 1.
+fn hsl_to_color(color: Hsl, alpha: f64) -> Color {
+    let [r, g, b] = color.to_rgb();
+    Color::from_rgba(r as f32, g as f32, b as f32, alpha.clamp(0f64, 1f64) as f32)
+        .unwrap_or(Color::BLACK)
+}
+
+/// Rasterizes the lines of a tree into a PNG image.
+///
+/// The tree is scaled to fit into the image while keeping its aspect ratio
+/// and centered. Lines are anti-aliased and use the same width (scaled) and
+/// color per level as the SVG output. Leaves are drawn on top of the
+/// branches. The background is transparent.
+///
+/// # Arguments
+///
+/// * `tree` - The tree to render.
+/// * `width` - Width of the image in pixels.
+/// * `height` - Height of the image in pixels.
+/// * `palette` - Colors of the branches, green shades if `None`.
+pub fn render_png(
+    tree: &Tree,
+    width: u32,
+    height: u32,
+    palette: Option<&Palette>,
+) -> Result<Vec<u8>, RasterError> {
+    rasterize(tree, width, height, None, palette, |_| 1f64)?
+        .encode_png()
+        .map_err(|e| RasterError::Encoding(e.to_string()))
+}
+
+/// Rasterizes the lines of a tree, see [`render_png`].
+///
+/// # Arguments
+///
+/// * `background` - Background color, transparent if `None`.
+/// * `palette` - Colors of the branches, green shades if `None`.
+/// * `progress` - How far the lines of a level (argument) are drawn, between
+///   0 (not at all) and 1 (completely). Leaves fade in with the last level.
+pub(crate) fn rasterize(
+    tree: &Tree,
+    width: u32,
+    height: u32,
+    background: Option<Color>,
+    palette: Option<&Palette>,
+    progress: impl Fn(usize) -> f64,
+) -> Result<Pixmap, RasterError> {
+    if u64::from(width) * u64::from(height) > MAX_PIXELS {
+        return Err(RasterError::InvalidSize { width, height });
+    }
+    let mut pixmap =
+        Pixmap::new(width, height).ok_or(RasterError::InvalidSize { width, height })?;
+    if let Some(background) = background {
+        pixmap.fill(background);
+    }
+
+    // Scale the tree to the image, keeping the aspect ratio
+    let rect = tree.bounding_rect;
+    let available_width = (width as f64 - 2f64 * PADDING).max(1f64);
+    let available_height = (height as f64 - 2f64 * PADDING).max(1f64);
+    let scale = (available_width / rect.size.width.max(f64::EPSILON))
+        .min(available_height / rect.size.height.max(f64::EPSILON));
+    let offset_x = (width as f64 - rect.size.width * scale) / 2f64 - rect.left_upper.x * scale;
+    let offset_y = (height as f64 - rect.size.height * scale) / 2f64 - rect.left_upper.y * scale;
+    let transform = Transform::from_row(
+        scale as f32,
+        0f32,
+        0f32,
+        scale as f32,
+        offset_x as f32,
+        offset_y as f32,
+    );
+
+    let max_level = tree.lines.len().saturating_sub(1);
+    for (level, lines) in tree.lines.iter().enumerate() {
+        let progress = progress(level).clamp(0f64, 1f64);
+        if progress <= 0f64 {
+            continue;
+        }
+
+        let mut builder = PathBuilder::new();
+        for line in lines.iter() {
+            let end_x = line.start.x + (line.end.x - line.start.x) * progress;
+            let end_y = line.start.y + (line.end.y - line.start.y) * progress;
+            builder.move_to(line.start.x as f32, line.start.y as f32);
+            builder.line_to(end_x as f32, end_y as f32);
+        }
+        let Some(path) = builder.finish() else {
+            continue;
+        };
+
+        let setting = LineSetting::new(level, max_level);
+        let color = match palette {
+            Some(palette) => palette.color(level, max_level),
+            None => Hsl::new(HUE, 100u8, setting.lightness),
+        };
+        let mut paint = Paint::default();
+        paint.set_color(hsl_to_color(color, 1f64));
+        paint.anti_alias = true;
+
+        // The width is given in tree units, so the transform scales it as well
+        let stroke = Stroke {
+            width: setting.width as f32,
+            line_cap: LineCap::Butt,
+            ..Default::default()
+        };
+        pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+    }
+
+    let leaf_progress = progress(max_level).clamp(0f64, 1f64);
+    if leaf_progress > 0f64 {
+        for leaf in tree.leaves.iter() {
+            let mut builder = PathBuilder::new();
+            for polygon in leaf.outline().iter() {
+                let Some((first, rest)) = polygon.split_first() else {
+                    continue;
+                };
+                builder.move_to(first.x as f32, first.y as f32);
+                rest.iter()
+                    .for_each(|point| builder.line_to(point.x as f32, point.y as f32));
+                builder.close();
+            }
+            let Some(path) = builder.finish() else {
+                continue;
+            };
+
+            let mut paint = Paint::default();
+            paint.set_color(hsl_to_color(leaf.color, leaf_progress));
+            paint.anti_alias = true;
+            pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+        }
+    }
+
+    Ok(pixmap)
+}
+
+/// Generates a fractal tree and rasterizes it into a PNG image.
+///
+/// See [`tree`] and [`render_png`] for the arguments.
+pub fn tree_png<S: AsRef<str>>(
+    seed: S,
+    levels: usize,
+    trunk_length: f64,
+    parameters: &TreeParameters,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, RasterError> {
+    render_png(
+        &tree(seed, levels, trunk_length, parameters),
+        width,
+        height,
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_has_requested_size() {
+        let png = tree_png("png", 5, 100f64, &TreeParameters::default(), 64, 48).unwrap();
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (64, 48));
+
+        // Something was drawn on the transparent background
+        assert!(pixmap.pixels().iter().any(|pixel| pixel.alpha() > 0));
+        assert!(pixmap.pixels().iter().any(|pixel| pixel.alpha() == 0));
+    }
+
+    #[test]
+    fn invalid_sizes_are_rejected() {
+        let tree = tree("png", 3, 100f64, &TreeParameters::default());
+        for (width, height) in [
+            (0u32, 10u32),
+            (10u32, 0u32),
+            (100_000u32, 100_000u32),
+            (1u32, u32::MAX),
+        ] {
+            assert!(matches!(
+                render_png(&tree, width, height, None),
+                Err(RasterError::InvalidSize { width: w, height: h }) if (w, h) == (width, height)
+            ));
+        }
+    }
+}
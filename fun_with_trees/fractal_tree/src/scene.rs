@@ -0,0 +1,273 @@
+use rand::Rng;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+
+use crate::*;
+
+/// Distance between ground line and horizon relative to the scene's width
+const HORIZON_HEIGHT: f64 = 0.15f64;
+
+/// Scale of trees at the horizon
+const HORIZON_SCALE: f64 = 0.3f64;
+
+/// Opacity of trees at the horizon
+const HORIZON_OPACITY: f64 = 0.35f64;
+
+/// A tree placed in a scene
+pub struct SceneTree {
+    pub tree: Tree,
+
+    /// Position of the root of the trunk in the scene
+    pub position: Vector2d,
+
+    /// Factor by which the tree is enlarged (> 1) or shrunk (< 1)
+    pub scale: f64,
+
+    /// Distance from the viewer, 0 at the ground line, 1 at the horizon
+    pub depth: f64,
+}
+
+impl SceneTree {
+    /// Opacity of the tree, trees fade with increasing depth.
+    pub fn opacity(&self) -> f64 {
+        1f64 - (1f64 - HORIZON_OPACITY) * self.depth
+    }
+
+    /// Bounding rectangle of the tree in the scene.
+    pub fn bounding_rect(&self) -> Rect {
+        let place = |point: Vector2d| Vector2d {
+            x: self.position.x + point.x * self.scale,
+            y: self.position.y + point.y * self.scale,
+        };
+        let left_upper = place(self.tree.bounding_rect.left_upper);
+        let right_lower = place(self.tree.bounding_rect.right_lower);
+
+        Rect {
+            left_upper,
+            right_lower,
+            size: Size {
+                width: right_lower.x - left_upper.x,
+                height: right_lower.y - left_upper.y,
+            },
+        }
+    }
+}
+
+/// A landscape with trees.
+///
+/// The ground line is at y = 0 and spans the width of the scene, the horizon
+/// is above it. Trees stand between ground line and horizon, trees further
+/// away are drawn behind those closer to the viewer.
+pub struct Scene {
+    pub trees: Vec<SceneTree>,
+
+    /// Width of the ground line
+    pub width: f64,
+
+    /// Distance between ground line and horizon
+    pub horizon_height: f64,
+}
+
+impl Scene {
+    /// Creates an empty scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the ground line, the horizon height depends on it.
+    pub fn new(width: f64) -> Self {
+        Scene {
+            trees: Vec::new(),
+            width,
+            horizon_height: width * HORIZON_HEIGHT,
+        }
+    }
+
+    /// Places a tree in the scene. The depth of the tree results from its
+    /// distance to the ground line.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree to place.
+    /// * `position` - Position of the root of the trunk.
+    /// * `scale` - Factor by which the tree is enlarged or shrunk.
+    pub fn add(&mut self, tree: Tree, position: Vector2d, scale: f64) {
+        let depth = if self.horizon_height > 0f64 {
+            (-position.y / self.horizon_height).clamp(0f64, 1f64)
+        } else {
+            0f64
+        };
+
+        self.trees.push(SceneTree {
+            tree,
+            position,
+            scale,
+            depth,
+        });
+    }
+
+    /// Generates a forest with randomly placed trees.
+    ///
+    /// Every tree gets its own seed derived from `seed`. Trees further away
+    /// are smaller (perspective).
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed for the random number generator.
+    /// * `trees` - Number of trees.
+    /// * `width` - Width of the scene.
+    /// * `levels` - Number of levels of the trees.
+    /// * `trunk_length` - Length of the trunk of trees at the ground line.
+    /// * `parameters` - Parameters influencing the visual appearance of the trees.
+    pub fn forest<S: AsRef<str>>(
+        seed: S,
+        trees: usize,
+        width: f64,
+        levels: usize,
+        trunk_length: f64,
+        parameters: &TreeParameters,
+    ) -> Self {
+        let mut rng: Pcg64 = Seeder::from((seed.as_ref(), "scene")).make_rng();
+        let mut scene = Scene::new(width);
+
+        for index in 0..trees {
+            let x = rng.gen_range(0f64..=width);
+            let depth = rng.gen_range(0f64..1f64);
+            let tree_seed = format!("{} {index}", seed.as_ref());
+            scene.add(
+                tree(tree_seed, levels, trunk_length, parameters),
+                Vector2d {
+                    x,
+                    y: -depth * scene.horizon_height,
+                },
+                1f64 - (1f64 - HORIZON_SCALE) * depth,
+            );
+        }
+
+        scene
+    }
+
+    /// Trees ordered from the horizon to the ground line, the order in which
+    /// they have to be drawn.
+    pub fn trees_back_to_front(&self) -> Vec<&SceneTree> {
+        let mut trees: Vec<&SceneTree> = self.trees.iter().collect();
+        trees.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        trees
+    }
+
+    /// Bounding rectangle of ground line, horizon and all trees.
+    pub fn bounding_rect(&self) -> Rect {
+        let mut bounding_rect: Rect = Default::default();
+        bounding_rect += &Line {
+            start: Vector2d {
+                x: 0f64,
+                y: -self.horizon_height,
+            },
+            end: Vector2d {
+                x: self.width,
+                y: 0f64,
+            },
+        };
+        for tree in self.trees.iter() {
+            bounding_rect += &tree.bounding_rect();
+        }
+
+        bounding_rect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Vector2d {
+        Vector2d { x, y }
+    }
+
+    /// Tree with a single vertical trunk of length 10
+    fn trunk() -> Tree {
+        Tree::from(vec![vec![Line {
+            start: point(0f64, 0f64),
+            end: point(0f64, -10f64),
+        }]])
+    }
+
+    #[test]
+    fn depth_depends_on_distance_to_ground_line() {
+        let mut scene = Scene::new(100f64);
+        assert_eq!(scene.horizon_height, 15f64);
+        for y in [-7.5f64, -100f64, 10f64] {
+            scene.add(trunk(), point(50f64, y), 1f64);
+        }
+        let depths: Vec<f64> = scene.trees.iter().map(|tree| tree.depth).collect();
+        assert_eq!(depths, [0.5f64, 1f64, 0f64]);
+
+        // Trees fade with their depth
+        assert_eq!(scene.trees[1].opacity(), HORIZON_OPACITY);
+        assert_eq!(scene.trees[2].opacity(), 1f64);
+
+        // Scenes without width have no room between ground line and horizon
+        let mut flat = Scene::new(0f64);
+        flat.add(trunk(), point(0f64, -5f64), 1f64);
+        assert_eq!(flat.trees[0].depth, 0f64);
+    }
+
+    #[test]
+    fn trees_are_drawn_from_back_to_front() {
+        let mut scene = Scene::new(100f64);
+        for y in [-3f64, -12f64, 0f64, -6f64] {
+            scene.add(trunk(), point(0f64, y), 1f64);
+        }
+        let positions: Vec<f64> = scene
+            .trees_back_to_front()
+            .iter()
+            .map(|tree| tree.position.y)
+            .collect();
+        assert_eq!(positions, [-12f64, -6f64, -3f64, 0f64]);
+    }
+
+    #[test]
+    fn same_seed_results_in_same_forest() {
+        let parameters = TreeParameters::default();
+        let forest = |seed: &str| Scene::forest(seed, 5, 800f64, 4, 50f64, &parameters);
+        let (first, second, other) = (forest("forest"), forest("forest"), forest("other"));
+
+        assert_eq!(first.trees.len(), 5);
+        for (a, b) in first.trees.iter().zip(second.trees.iter()) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.scale, b.scale);
+            assert_eq!(a.tree.lines, b.tree.lines);
+        }
+        assert!(first
+            .trees
+            .iter()
+            .zip(other.trees.iter())
+            .any(|(a, b)| a.position != b.position));
+
+        // Trees stand between ground line and horizon, smaller further away
+        for tree in first.trees.iter() {
+            assert!((0f64..=800f64).contains(&tree.position.x));
+            assert!((-first.horizon_height..=0f64).contains(&tree.position.y));
+            assert!(tree.scale <= 1f64 && tree.scale >= HORIZON_SCALE);
+        }
+    }
+
+    #[test]
+    fn bounding_rect_contains_scaled_trees() {
+        let mut scene = Scene::new(100f64);
+        scene.add(trunk(), point(200f64, -5f64), 3f64);
+
+        let tree_rect = scene.trees[0].bounding_rect();
+        assert_eq!(tree_rect.left_upper, point(200f64, -35f64));
+        assert_eq!(tree_rect.right_lower, point(200f64, -5f64));
+
+        let rect = scene.bounding_rect();
+        assert_eq!(rect.left_upper, point(0f64, -35f64));
+        assert_eq!(rect.right_lower, point(200f64, 0f64));
+        assert_eq!((rect.size.width, rect.size.height), (200f64, 35f64));
+
+        // Without trees, the scene spans ground line and horizon
+        let empty = Scene::new(100f64).bounding_rect();
+        assert_eq!(empty.left_upper, point(0f64, -15f64));
+        assert_eq!(empty.right_lower, point(100f64, 0f64));
+    }
+}
@@ -1,94 +1,564 @@
-use serde::Serialize;
-use handlebars::{handlebars_helper, Handlebars};
+use std::{
+    fmt,
+    io::{self, Write},
+};
 
 use crate::*;
 
-/// Represents settings for a line in the tree image
-#[derive(Debug, Serialize)]
-pub struct LineSetting {
-    /// Lightness percentage (HSL color mode)
-    pub lightness: u8,
+/// Fill color of the ground between ground line and horizon in scenes
+const GROUND_COLOR: &str = "#eef3e2";
 
-    /// Width of the line
-    pub width: f64,
+/// Color of the horizon in scenes
+const HORIZON_COLOR: &str = "#c8d2bc";
+
+/// Color of the ground line in scenes
+const GROUND_LINE_COLOR: &str = "#7a8a6a";
+
+/// Width of ground line and horizon in scenes
+const GROUND_LINE_WIDTH: f64 = 2f64;
+
+/// Color of SVG elements
+#[derive(Debug, Clone, PartialEq)]
+pub enum Color {
+    /// HSL color, hue in degrees, saturation and lightness in percent
+    Hsl {
+        hue: f64,
+        saturation: u8,
+        lightness: u8,
+    },
+    /// Any color understood by SVG, e.g. `white` or `#336699`.
+    /// Quotes and markup characters are removed when writing.
+    Named(String),
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::Hsl {
+                hue,
+                saturation,
+                lightness,
+            } => write!(f, "hsl({hue}, {saturation}%, {lightness}%)"),
+            Color::Named(name) => f.write_str(&name.replace(['"', '<', '>', '&'], "")),
+        }
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(color: Hsl) -> Self {
+        Color::Hsl {
+            hue: color.hue,
+            saturation: color.saturation,
+            lightness: color.lightness,
+        }
+    }
+}
+
+/// Shape of the ends of lines
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }
+    }
+}
+
+/// Styling of the SVG output
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Hue of the lines (HSL color mode), the lightness depends on the level
+    pub hue: f64,
+
+    /// Saturation percentage of the lines (HSL color mode)
+    pub saturation: u8,
+
+    /// Background color, transparent if `None`
+    pub background: Option<Color>,
+
+    /// Shape of the ends of the lines
+    pub line_cap: LineCap,
+
+    /// Colors of the branches, overrides `hue` and `saturation`
+    pub palette: Option<Palette>,
+}
+
+impl SvgOptions {
+    /// Color of the branches of a level
+    fn stroke(&self, level: usize, max_level: usize) -> Color {
+        match &self.palette {
+            Some(palette) => palette.color(level, max_level).into(),
+            None => Color::Hsl {
+                hue: self.hue,
+                saturation: self.saturation,
+                lightness: LineSetting::new(level, max_level).lightness,
+            },
+        }
+    }
 }
 
-impl LineSetting {
-    /// Creates line settings from a given tree branching level.
-    /// 
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            hue: 90f64,
+            saturation: 100u8,
+            background: None,
+            line_cap: LineCap::Butt,
+            palette: None,
+        }
+    }
+}
+
+/// Writes SVG elements directly into an [`io::Write`].
+///
+/// Nothing is buffered, so wrap unbuffered targets like files
+/// in an [`io::BufWriter`].
+pub struct SvgWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> SvgWriter<W> {
+    /// Starts an SVG document showing the given area.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - Target of the SVG document.
+    /// * `view_box` - Area of the drawing that is visible, also used as size of the image.
+    /// * `background` - Background color, transparent if `None`.
+    pub fn begin(mut out: W, view_box: &Rect, background: Option<&Color>) -> io::Result<Self> {
+        let Rect {
+            left_upper, size, ..
+        } = view_box;
+        writeln!(
+            out,
+            r#"<svg version="1.1" xmlns="http://www.w3.org/2000/svg" width="{:.4}" height="{:.4}" viewBox="{:.4} {:.4} {:.4} {:.4}">"#,
+            size.width, size.height, left_upper.x, left_upper.y, size.width, size.height
+        )?;
+
+        if let Some(background) = background {
+            writeln!(
+                out,
+                r#"<rect x="{:.4}" y="{:.4}" width="{:.4}" height="{:.4}" fill="{background}" />"#,
+                left_upper.x, left_upper.y, size.width, size.height
+            )?;
+        }
+
+        Ok(Self { out })
+    }
+
+    /// Writes lines sharing the same style as a single path.
+    ///
     /// # Arguments
-    /// 
-    /// * `level` - The current level.
-    /// * `max_level` - The total number of levels in the tree.
-    fn new(level: usize, max_level: usize) -> Self {
-        const LEVEL_TO_LIGHTNESS_FACTOR: u8 = 3u8;
-        const LEVEL_TO_WEIGHT_FACTOR: f64 = 3f64;
-
-        LineSetting {
-            lightness: level as u8 * LEVEL_TO_LIGHTNESS_FACTOR,
-            width: (max_level + 1 - level) as f64 * LEVEL_TO_WEIGHT_FACTOR / 2f64,
+    ///
+    /// * `lines` - The lines to draw, paths without lines are skipped.
+    /// * `stroke` - Color of the lines.
+    /// * `width` - Width of the lines.
+    /// * `line_cap` - Shape of the ends of the lines.
+    pub fn path(
+        &mut self,
+        lines: &[Line],
+        stroke: &Color,
+        width: f64,
+        line_cap: LineCap,
+    ) -> io::Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        self.out.write_all(br#"<path d=""#)?;
+        for (index, line) in lines.iter().enumerate() {
+            let separator = if index == 0 { "" } else { " " };
+            write!(
+                self.out,
+                "{separator}M{:.4},{:.4} L{:.4},{:.4}",
+                line.start.x, line.start.y, line.end.x, line.end.y
+            )?;
         }
+        writeln!(
+            self.out,
+            r#"" fill="none" stroke="{stroke}" stroke-width="{width:.4}" stroke-linecap="{}" />"#,
+            line_cap.as_str()
+        )
+    }
+
+    /// Writes closed polygons sharing the same color as a single filled path.
+    ///
+    /// # Arguments
+    ///
+    /// * `polygons` - The polygons to fill, paths without polygons are skipped.
+    /// * `fill` - Color of the polygons.
+    /// * `animation` - Value of the CSS `animation` property of the path, if any.
+    pub fn shapes(
+        &mut self,
+        polygons: &[Vec<Vector2d>],
+        fill: &Color,
+        animation: Option<&str>,
+    ) -> io::Result<()> {
+        if polygons.iter().all(|p| p.is_empty()) {
+            return Ok(());
+        }
+
+        self.out.write_all(br#"<path d=""#)?;
+        let mut separator = "";
+        for polygon in polygons.iter().filter(|p| !p.is_empty()) {
+            for (index, point) in polygon.iter().enumerate() {
+                let command = if index == 0 { "M" } else { "L" };
+                write!(
+                    self.out,
+                    "{separator}{command}{:.4},{:.4}",
+                    point.x, point.y
+                )?;
+                separator = " ";
+            }
+            self.out.write_all(b" Z")?;
+        }
+        write!(self.out, r#"" fill="{fill}""#)?;
+        if let Some(animation) = animation {
+            write!(self.out, r#" style="animation: {animation}""#)?;
+        }
+        writeln!(self.out, " />")
+    }
+
+    /// Writes a CSS style sheet.
+    pub fn style(&mut self, css: &str) -> io::Result<()> {
+        writeln!(self.out, "<style>{css}</style>")
+    }
+
+    /// Writes lines sharing the same style as a group of separate paths, so
+    /// that CSS can animate their growth.
+    ///
+    /// All paths have a normalized length of 1 and a dash pattern of 1, so
+    /// animating `stroke-dashoffset` from 1 to 0 draws every line from its
+    /// start to its end at the same time.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The lines to draw, groups without lines are skipped.
+    /// * `stroke` - Color of the lines.
+    /// * `width` - Width of the lines.
+    /// * `line_cap` - Shape of the ends of the lines.
+    /// * `animation` - Value of the CSS `animation` property of the group.
+    pub fn growing_paths(
+        &mut self,
+        lines: &[Line],
+        stroke: &Color,
+        width: f64,
+        line_cap: LineCap,
+        animation: &str,
+    ) -> io::Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(
+            self.out,
+            r#"<g fill="none" stroke="{stroke}" stroke-width="{width:.4}" stroke-linecap="{}" stroke-dasharray="1" style="animation: {animation}">"#,
+            line_cap.as_str()
+        )?;
+        for line in lines.iter() {
+            writeln!(
+                self.out,
+                r#"<path pathLength="1" d="M{:.4},{:.4} L{:.4},{:.4}" />"#,
+                line.start.x, line.start.y, line.end.x, line.end.y
+            )?;
+        }
+        writeln!(self.out, "</g>")
+    }
+
+    /// Starts a group of elements that are moved, scaled and faded together.
+    /// Every group must be closed with [`SvgWriter::end_group`].
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Distance by which the elements are moved.
+    /// * `scale` - Factor by which the elements are enlarged or shrunk.
+    /// * `opacity` - Opacity of the group between 0 and 1.
+    pub fn begin_group(&mut self, offset: Vector2d, scale: f64, opacity: f64) -> io::Result<()> {
+        writeln!(
+            self.out,
+            r#"<g transform="translate({:.4} {:.4}) scale({scale:.4})" opacity="{opacity:.4}">"#,
+            offset.x, offset.y
+        )
+    }
+
+    /// Closes a group started with [`SvgWriter::begin_group`].
+    pub fn end_group(&mut self) -> io::Result<()> {
+        writeln!(self.out, "</g>")
+    }
+
+    /// Closes the SVG document and returns the target.
+    pub fn end(mut self) -> io::Result<W> {
+        writeln!(self.out, "</svg>")?;
+        Ok(self.out)
     }
 }
 
-/// Represent a collection of all lines of a common tree level.
-/// 
-/// Because all lines are on the same tree level, they share
-/// the same settings.
-#[derive(Serialize)]
-struct SettingsWithLines<'a> {
-    settings: LineSetting,
-    lines: &'a Vec<Line>,
-}
-
-/// Contains all tree levels with their branches and bounding rect.
-/// 
-/// Used for SVG generation
-#[derive(Serialize)]
-struct TreeGenerationData<'a> {
-    lines: Vec<SettingsWithLines<'a>>,
-    bounding_rect: Rect,
-}
-
-const SVG_TEMPLATE: &str = r#"
-<svg version="1.1"
-     baseProfile="full"
-     xmlns="http://www.w3.org/2000/svg"
-     xmlns:xlink="http://www.w3.org/1999/xlink"
-     xmlns:ev="http://www.w3.org/2001/xml-events"
-     width="{{format this.bounding_rect.size.width}}" height="{{format this.bounding_rect.size.height}}" 
-     viewBox="{{format this.bounding_rect.left_upper.x}} {{format this.bounding_rect.left_upper.y}} {{format this.bounding_rect.size.width}} {{format this.bounding_rect.size.height}} ">
-    <g>
-    {{#each this.lines}}
-        <path d="{{#each lines}}M{{format start.x}},{{format start.y}} L{{format end.x}}{{format end.y}}z{{/each}} " stroke="hsl(90, 100%, {{settings.lightness}}%)" stroke-width="{{format settings.width}}" />
-    {{/each}}
-    </g>
-</svg>"#;
-
-pub fn tree_svg<S: AsRef<str>>(seed: S, levels: usize, trunk_length: f64) -> String {
-    // Create handlebar template engine
-    let mut handlebars = Handlebars::new();
-    handlebars
-        .register_template_string("svg", SVG_TEMPLATE)
-        .unwrap();
-    handlebars_helper!(format: |v: f64| format!("{v:.4}"));
-    handlebars.register_helper("format", Box::new(format));
-    
-    // Generate tree
-    let tree_lines = tree(seed, levels, trunk_length);
-
-    // Convert tree data to data structure required for SVG template
-    let tree = TreeGenerationData {
-        lines: tree_lines.lines
+/// Writes the leaves of a tree, one path per color.
+fn write_leaves<W: Write>(
+    writer: &mut SvgWriter<W>,
+    leaves: &[Leaf],
+    animation: Option<&str>,
+) -> io::Result<()> {
+    let mut colors: Vec<Hsl> = Vec::new();
+    for leaf in leaves.iter() {
+        if !colors.contains(&leaf.color) {
+            colors.push(leaf.color);
+        }
+    }
+
+    for color in colors {
+        let polygons: Vec<Vec<Vector2d>> = leaves
             .iter()
-            .enumerate()
-            .map(|l| SettingsWithLines {
-                settings: LineSetting::new(l.0, tree_lines.lines.len() - 1),
-                lines: l.1
-            })
-            .collect(),
-        bounding_rect: tree_lines.bounding_rect,
+            .filter(|leaf| leaf.color == color)
+            .flat_map(|leaf| leaf.outline())
+            .collect();
+        writer.shapes(&polygons, &color.into(), animation)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the lines of a tree as SVG document.
+///
+/// Every level of the tree becomes a path with the width and lightness of
+/// its [`LineSetting`] (or the color of the palette). Leaves are drawn on
+/// top of the branches.
+pub fn write_svg<W: Write>(tree: &Tree, options: &SvgOptions, out: W) -> io::Result<W> {
+    let mut writer = SvgWriter::begin(out, &tree.bounding_rect, options.background.as_ref())?;
+    write_tree(&mut writer, tree, options)?;
+    writer.end()
+}
+
+/// Writes the branches (one path per level) and leaves of a tree.
+fn write_tree<W: Write>(
+    writer: &mut SvgWriter<W>,
+    tree: &Tree,
+    options: &SvgOptions,
+) -> io::Result<()> {
+    let max_level = tree.lines.len().saturating_sub(1);
+    for (level, lines) in tree.lines.iter().enumerate() {
+        let setting = LineSetting::new(level, max_level);
+        let stroke = options.stroke(level, max_level);
+        writer.path(lines, &stroke, setting.width, options.line_cap)?;
+    }
+
+    write_leaves(writer, &tree.leaves, None)
+}
+
+/// Writes a scene as SVG document.
+///
+/// The ground between ground line and horizon is filled, trees are drawn
+/// from back to front like in [`write_svg`] and fade with their depth.
+pub fn write_scene_svg<W: Write>(scene: &Scene, options: &SvgOptions, out: W) -> io::Result<W> {
+    // Make room for the lower half of the ground line
+    let mut view_box = scene.bounding_rect();
+    view_box += &Line {
+        start: Vector2d {
+            x: 0f64,
+            y: GROUND_LINE_WIDTH,
+        },
+        end: Vector2d {
+            x: 0f64,
+            y: GROUND_LINE_WIDTH,
+        },
     };
+    let mut writer = SvgWriter::begin(out, &view_box, options.background.as_ref())?;
+
+    let horizon = -scene.horizon_height;
+    let corner = |x: f64, y: f64| Vector2d { x, y };
+    writer.shapes(
+        &[vec![
+            corner(0f64, horizon),
+            corner(scene.width, horizon),
+            corner(scene.width, 0f64),
+            corner(0f64, 0f64),
+        ]],
+        &Color::Named(GROUND_COLOR.to_string()),
+        None,
+    )?;
+    for (y, color) in [(horizon, HORIZON_COLOR), (0f64, GROUND_LINE_COLOR)] {
+        let line = Line {
+            start: corner(0f64, y),
+            end: corner(scene.width, y),
+        };
+        writer.path(
+            &[line],
+            &Color::Named(color.to_string()),
+            GROUND_LINE_WIDTH,
+            LineCap::Butt,
+        )?;
+    }
 
-    handlebars.render("svg", &tree).unwrap()
+    for tree in scene.trees_back_to_front() {
+        writer.begin_group(tree.position, tree.scale, tree.opacity())?;
+        write_tree(&mut writer, &tree.tree, options)?;
+        writer.end_group()?;
+    }
+
+    writer.end()
+}
+
+/// Writes the lines of a tree as SVG document in which the levels grow one
+/// after another (CSS animation).
+///
+/// Styling is the same as in [`write_svg`], leaves fade in while the last
+/// level grows.
+pub fn write_animated_svg<W: Write>(
+    tree: &Tree,
+    options: &SvgOptions,
+    animation: &AnimationOptions,
+    out: W,
+) -> io::Result<W> {
+    let mut writer = SvgWriter::begin(out, &tree.bounding_rect, options.background.as_ref())?;
+    writer.style(concat!(
+        "@keyframes grow { from { stroke-dashoffset: 1; } to { stroke-dashoffset: 0; } }\n",
+        "@keyframes bloom { from { opacity: 0; } to { opacity: 1; } }"
+    ))?;
+
+    let max_level = tree.lines.len().saturating_sub(1);
+    let level_duration = animation.level_duration(tree.lines.len());
+    for (level, lines) in tree.lines.iter().enumerate() {
+        let setting = LineSetting::new(level, max_level);
+        let stroke = options.stroke(level, max_level);
+
+        // `both` hides the level before its delay and keeps it after growing
+        let css = format!(
+            "grow {level_duration:.4}s {} {:.4}s both",
+            animation.easing.css_timing_function(),
+            level as f64 * level_duration
+        );
+        writer.growing_paths(lines, &stroke, setting.width, options.line_cap, &css)?;
+    }
+
+    // Leaves fade in while the last level grows
+    let css = format!(
+        "bloom {level_duration:.4}s {} {:.4}s both",
+        animation.easing.css_timing_function(),
+        max_level as f64 * level_duration
+    );
+    write_leaves(&mut writer, &tree.leaves, Some(&css))?;
+
+    writer.end()
+}
+
+/// Renders the lines of a tree as SVG.
+pub fn render_svg(tree: &Tree, options: &SvgOptions) -> String {
+    let buffer = write_svg(tree, options, Vec::new()).expect("writing to memory does not fail");
+    String::from_utf8(buffer).expect("SVG output is valid UTF-8")
+}
+
+/// Generates a fractal tree and renders it as SVG.
+///
+/// See [`tree`] for the arguments.
+pub fn tree_svg<S: AsRef<str>>(
+    seed: S,
+    levels: usize,
+    trunk_length: f64,
+    parameters: &TreeParameters,
+) -> String {
+    render_svg(
+        &tree(seed, levels, trunk_length, parameters),
+        &SvgOptions::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values of all `d` attributes of a document
+    fn path_data(svg: &str) -> Vec<&str> {
+        svg.split(r#" d=""#)
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .collect()
+    }
+
+    #[test]
+    fn coordinates_are_separated() {
+        let lines = [
+            Line {
+                start: Vector2d { x: 0f64, y: 0f64 },
+                end: Vector2d {
+                    x: 1.5f64,
+                    y: -2f64,
+                },
+            },
+            Line {
+                start: Vector2d {
+                    x: 1.5f64,
+                    y: -2f64,
+                },
+                end: Vector2d { x: -3f64, y: 4f64 },
+            },
+        ];
+        let mut writer = SvgWriter::begin(Vec::new(), &Rect::default(), None).unwrap();
+        writer
+            .path(
+                &lines,
+                &Color::Named("black".to_string()),
+                1f64,
+                LineCap::Butt,
+            )
+            .unwrap();
+        let svg = String::from_utf8(writer.end().unwrap()).unwrap();
+
+        assert_eq!(
+            path_data(&svg),
+            vec!["M0.0000,0.0000 L1.5000,-2.0000 M1.5000,-2.0000 L-3.0000,4.0000"]
+        );
+    }
+
+    #[test]
+    fn paths_of_trees_are_valid() {
+        let tree = tree("svg", 3, 100f64, &TreeParameters::default());
+        let svg = render_svg(&tree, &SvgOptions::default());
+
+        let paths = path_data(&svg);
+        assert_eq!(paths.len(), tree.lines.len());
+        for (d, lines) in paths.iter().zip(tree.lines.iter()) {
+            let commands: Vec<&str> = d.split(' ').collect();
+            assert_eq!(commands.len(), 2 * lines.len());
+            for (index, command) in commands.iter().enumerate() {
+                let expected = if index % 2 == 0 { "M" } else { "L" };
+                let (x, y) = command
+                    .strip_prefix(expected)
+                    .and_then(|point| point.split_once(','))
+                    .unwrap_or_else(|| panic!("invalid command '{command}' in '{d}'"));
+                assert!(x.parse::<f64>().is_ok() && y.parse::<f64>().is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn scenes_contain_ground_and_every_tree() {
+        let scene = Scene::forest("scene", 3, 500f64, 3, 50f64, &TreeParameters::default());
+        let svg =
+            String::from_utf8(write_scene_svg(&scene, &SvgOptions::default(), Vec::new()).unwrap())
+                .unwrap();
+
+        assert!(svg.starts_with("<svg") || svg.starts_with("<?xml"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        // Ground, horizon, ground line and a path per level of every tree
+        assert_eq!(path_data(&svg).len(), 3 + 3 * 3);
+
+        // Trees further away are drawn first and fade more
+        let opacities: Vec<f64> = svg
+            .split(r#"opacity=""#)
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next()?.parse().ok())
+            .collect();
+        assert_eq!(opacities.len(), 3);
+        assert!(opacities.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
 }
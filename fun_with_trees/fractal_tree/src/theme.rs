@@ -0,0 +1,453 @@
+use std::{f64::consts::PI, fmt, str::FromStr};
+
+use rand::Rng;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+
+use crate::*;
+
+/// Number of points of the outline of a leaf
+const LEAF_POINTS: usize = 12usize;
+
+/// Number of petals of a blossom
+const PETALS: usize = 5usize;
+
+/// Number of points of round outlines (petals, snow)
+const CIRCLE_POINTS: usize = 10usize;
+
+/// Maximum random change of the leaves' angle (degrees, total range)
+const LEAF_ANGLE_JITTER: f64 = 60f64;
+
+/// Color in HSL color mode, hue in degrees, saturation and lightness in percent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub hue: f64,
+    pub saturation: u8,
+    pub lightness: u8,
+}
+
+impl Hsl {
+    pub const fn new(hue: f64, saturation: u8, lightness: u8) -> Self {
+        Self {
+            hue,
+            saturation,
+            lightness,
+        }
+    }
+
+    /// Converts the color to red, green and blue (sRGB), each between 0 and 1.
+    pub fn to_rgb(self) -> [f64; 3] {
+        let lightness = (self.lightness as f64 / 100f64).clamp(0f64, 1f64);
+        let saturation = (self.saturation as f64 / 100f64).clamp(0f64, 1f64);
+        let chroma = (1f64 - (2f64 * lightness - 1f64).abs()) * saturation;
+        let sector = self.hue.rem_euclid(360f64) / 60f64;
+        let x = chroma * (1f64 - (sector % 2f64 - 1f64).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0f64),
+            1 => (x, chroma, 0f64),
+            2 => (0f64, chroma, x),
+            3 => (0f64, x, chroma),
+            4 => (x, 0f64, chroma),
+            _ => (chroma, 0f64, x),
+        };
+        let m = lightness - chroma / 2f64;
+
+        [r + m, g + m, b + m]
+    }
+
+    /// Mixes two colors, the hue takes the shorter way around the color wheel.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The color to mix with.
+    /// * `t` - Share of `other`, between 0 (only `self`) and 1 (only `other`).
+    pub fn mix(self, other: Hsl, t: f64) -> Hsl {
+        let t = t.clamp(0f64, 1f64);
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        let hue_difference = (other.hue - self.hue + 540f64).rem_euclid(360f64) - 180f64;
+
+        Hsl {
+            hue: (self.hue + hue_difference * t).rem_euclid(360f64),
+            saturation: lerp(self.saturation, other.saturation),
+            lightness: lerp(self.lightness, other.lightness),
+        }
+    }
+}
+
+/// Colors of the branches, a gradient from the trunk to the tips.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// Color of the trunk
+    pub trunk: Hsl,
+
+    /// Color of the outermost branches
+    pub tips: Hsl,
+}
+
+impl Palette {
+    /// Color of the branches of a level.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The current level.
+    /// * `max_level` - The total number of levels in the tree.
+    pub fn color(&self, level: usize, max_level: usize) -> Hsl {
+        if max_level == 0 {
+            return self.trunk;
+        }
+
+        self.trunk.mix(self.tips, level as f64 / max_level as f64)
+    }
+}
+
+/// Shape of the foliage at the tips of the branches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafShape {
+    /// Pointed leaf in the direction of the branch
+    Leaf,
+    /// Flower with five round petals
+    Blossom,
+    /// Round lump of snow
+    Snow,
+}
+
+/// Settings for growing foliage at the tips of the branches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Foliage {
+    /// Shape of all leaves
+    pub shape: LeafShape,
+
+    /// Colors of the leaves, every leaf gets a random one
+    pub colors: Vec<Hsl>,
+
+    /// Size of the leaves relative to the length of the branch they grow on
+    pub size: f64,
+
+    /// Probability that a leaf grows on a tip, between 0 and 1
+    pub density: f64,
+}
+
+/// A single leaf (or blossom) of a tree
+#[derive(Debug, Clone, Copy)]
+pub struct Leaf {
+    /// Point the leaf grows out of
+    pub position: Vector2d,
+
+    /// Direction of the leaf in radians
+    pub angle: f64,
+
+    /// Length of the leaf
+    pub size: f64,
+
+    pub color: Hsl,
+
+    pub shape: LeafShape,
+}
+
+impl Leaf {
+    /// Calculates the outline of the leaf as closed polygons.
+    pub fn outline(&self) -> Vec<Vec<Vector2d>> {
+        let (sin, cos) = self.angle.sin_cos();
+        let point = |x: f64, y: f64| {
+            self.position
+                + Vector2d {
+                    x: x * cos - y * sin,
+                    y: x * sin + y * cos,
+                }
+        };
+        let circle = |center_x: f64, center_y: f64, radius: f64| -> Vec<Vector2d> {
+            (0..CIRCLE_POINTS)
+                .map(|i| {
+                    let angle = 2f64 * PI * i as f64 / CIRCLE_POINTS as f64;
+                    point(
+                        center_x + radius * angle.cos(),
+                        center_y + radius * angle.sin(),
+                    )
+                })
+                .collect()
+        };
+
+        match self.shape {
+            LeafShape::Leaf => {
+                // Lens shape, first the upper and then the lower side
+                let half_width = |t: f64| 0.2f64 * self.size * (PI * t).sin();
+                let upper = (0..=LEAF_POINTS).map(|i| {
+                    let t = i as f64 / LEAF_POINTS as f64;
+                    point(t * self.size, -half_width(t))
+                });
+                let lower = (1..LEAF_POINTS).rev().map(|i| {
+                    let t = i as f64 / LEAF_POINTS as f64;
+                    point(t * self.size, half_width(t))
+                });
+                vec![upper.chain(lower).collect()]
+            }
+            LeafShape::Blossom => {
+                let radius = 0.25f64 * self.size;
+                (0..PETALS)
+                    .map(|i| {
+                        let angle = 2f64 * PI * i as f64 / PETALS as f64;
+                        circle(radius * angle.cos(), radius * angle.sin(), radius)
+                    })
+                    .collect()
+            }
+            LeafShape::Snow => vec![circle(0f64, 0f64, 0.3f64 * self.size)],
+        }
+    }
+}
+
+impl Tree {
+    /// Grows foliage at the tips (branches of the last level) of the tree and
+    /// enlarges the bounding rectangle so that it contains the leaves.
+    ///
+    /// The same seed always results in the same leaves. Use the seed of the
+    /// tree, the random numbers differ from those of the branches anyway.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed for the random number generator.
+    /// * `foliage` - Shape, colors, size and density of the leaves.
+    pub fn grow_foliage<S: AsRef<str>>(&mut self, seed: S, foliage: &Foliage) {
+        let mut rng: Pcg64 = Seeder::from((seed.as_ref(), "foliage")).make_rng();
+        let Some(tips) = self.lines.last() else {
+            return;
+        };
+        if foliage.colors.is_empty() {
+            return;
+        }
+
+        for tip in tips.iter() {
+            // Always draw all random numbers so that every tip gets the same
+            // leaf, independent of the density
+            let grows = rng.gen_bool(foliage.density.clamp(0f64, 1f64));
+            let angle_change = (rng.gen_range(-0.5f64..0.5f64) * LEAF_ANGLE_JITTER).to_radians();
+            let size_factor = rng.gen_range(0.75f64..1.25f64);
+            let color = foliage.colors[rng.gen_range(0..foliage.colors.len())];
+            if !grows {
+                continue;
+            }
+
+            let dx = tip.end.x - tip.start.x;
+            let dy = tip.end.y - tip.start.y;
+            let leaf = Leaf {
+                position: tip.end,
+                angle: dy.atan2(dx) + angle_change,
+                size: (dx * dx + dy * dy).sqrt() * foliage.size * size_factor,
+                color,
+                shape: foliage.shape,
+            };
+
+            for point in leaf.outline().iter().flatten() {
+                self.bounding_rect += &Line {
+                    start: *point,
+                    end: *point,
+                };
+            }
+            self.leaves.push(leaf);
+        }
+    }
+}
+
+/// Predefined palettes and foliage for seasonal trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Autumn,
+    Winter,
+    CherryBlossom,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [
+        Season::Spring,
+        Season::Autumn,
+        Season::Winter,
+        Season::CherryBlossom,
+    ];
+
+    /// Name used by [`FromStr`] and [`fmt::Display`]
+    pub fn name(self) -> &'static str {
+        match self {
+            Season::Spring => "spring",
+            Season::Autumn => "autumn",
+            Season::Winter => "winter",
+            Season::CherryBlossom => "cherry-blossom",
+        }
+    }
+
+    /// Colors of the branches
+    pub fn palette(self) -> Palette {
+        let (trunk, tips) = match self {
+            Season::Spring => (Hsl::new(30f64, 35, 20), Hsl::new(95f64, 55, 40)),
+            Season::Autumn => (Hsl::new(25f64, 40, 15), Hsl::new(30f64, 45, 35)),
+            Season::Winter => (Hsl::new(220f64, 10, 20), Hsl::new(210f64, 15, 55)),
+            Season::CherryBlossom => (Hsl::new(15f64, 30, 15), Hsl::new(350f64, 25, 35)),
+        };
+
+        Palette { trunk, tips }
+    }
+
+    /// Leaves, blossoms or snow at the tips of the branches
+    pub fn foliage(self) -> Foliage {
+        let (shape, colors, size, density) = match self {
+            Season::Spring => (
+                LeafShape::Leaf,
+                vec![
+                    Hsl::new(95f64, 65, 50),
+                    Hsl::new(110f64, 55, 42),
+                    Hsl::new(80f64, 70, 55),
+                ],
+                1.2f64,
+                0.9f64,
+            ),
+            Season::Autumn => (
+                LeafShape::Leaf,
+                vec![
+                    Hsl::new(30f64, 90, 50),
+                    Hsl::new(15f64, 85, 45),
+                    Hsl::new(45f64, 95, 55),
+                    Hsl::new(0f64, 75, 40),
+                ],
+                1.2f64,
+                0.6f64,
+            ),
+            Season::Winter => (
+                LeafShape::Snow,
+                vec![Hsl::new(0f64, 0, 100), Hsl::new(200f64, 60, 95)],
+                1f64,
+                0.5f64,
+            ),
+            Season::CherryBlossom => (
+                LeafShape::Blossom,
+                vec![
+                    Hsl::new(340f64, 80, 85),
+                    Hsl::new(330f64, 70, 78),
+                    Hsl::new(350f64, 90, 92),
+                    Hsl::new(0f64, 0, 100),
+                ],
+                1.2f64,
+                0.95f64,
+            ),
+        };
+
+        Foliage {
+            shape,
+            colors,
+            size,
+            density,
+        }
+    }
+}
+
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Season {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Season::ALL
+            .into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Season::ALL.iter().map(|s| s.name()).collect();
+                format!(
+                    "unknown season '{name}', must be one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn foliage(density: f64) -> Foliage {
+        Foliage {
+            density,
+            ..Season::Autumn.foliage()
+        }
+    }
+
+    #[test]
+    fn leaves_grow_at_the_tips() {
+        let mut tree = tree("leaves", 6, 100f64, &TreeParameters::default());
+        tree.grow_foliage("leaves", &foliage(1f64));
+
+        let tips = tree.lines.last().unwrap();
+        assert_eq!(tree.leaves.len(), tips.len());
+        for (leaf, tip) in tree.leaves.iter().zip(tips.iter()) {
+            assert_eq!(leaf.position, tip.end);
+        }
+
+        // Leaves only grow at tips, so there are fewer with a lower density
+        let mut sparse = super::tree("leaves", 6, 100f64, &TreeParameters::default());
+        sparse.grow_foliage("leaves", &foliage(0.5f64));
+        assert!(sparse.leaves.len() < tips.len());
+        assert!(sparse
+            .leaves
+            .iter()
+            .all(|leaf| tips.iter().any(|tip| tip.end == leaf.position)));
+    }
+
+    #[test]
+    fn same_seed_grows_same_leaves() {
+        let leaves = |seed: &str| {
+            let mut tree = tree("tree", 6, 100f64, &TreeParameters::default());
+            tree.grow_foliage(seed, &foliage(0.5f64));
+            tree.leaves
+                .iter()
+                .map(|leaf| (leaf.position, leaf.angle, leaf.size, leaf.color))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(leaves("autumn"), leaves("autumn"));
+        assert_ne!(leaves("autumn"), leaves("spring"));
+    }
+
+    #[test]
+    fn leaves_enlarge_the_bounding_rect() {
+        let mut tree = tree("leaves", 4, 100f64, &TreeParameters::default());
+        let before = tree.bounding_rect;
+        tree.grow_foliage("leaves", &Season::Spring.foliage());
+        assert!(tree.bounding_rect.size.width > before.size.width);
+        assert!(tree.bounding_rect.size.height > before.size.height);
+
+        // Trees without levels have no tips
+        let mut empty = super::tree("leaves", 0, 100f64, &TreeParameters::default());
+        empty.grow_foliage("leaves", &Season::Spring.foliage());
+        assert!(empty.leaves.is_empty());
+    }
+
+    #[test]
+    fn seasons_are_parsed_by_name() {
+        for season in Season::ALL {
+            assert_eq!(season.name().parse(), Ok(season));
+            assert_eq!(season.to_string(), season.name());
+        }
+        assert_eq!("Cherry-Blossom".parse(), Ok(Season::CherryBlossom));
+        assert!("summer".parse::<Season>().is_err());
+    }
+
+    #[test]
+    fn palettes_go_from_trunk_to_tips() {
+        for season in Season::ALL {
+            let palette = season.palette();
+            assert_eq!(palette.color(0, 10), palette.trunk, "{season}");
+            assert_eq!(palette.color(10, 10), palette.tips, "{season}");
+            assert_eq!(palette.color(3, 0), palette.trunk, "{season}");
+            assert!(!season.foliage().colors.is_empty(), "{season}");
+        }
+    }
+
+    #[test]
+    fn colors_mix_the_short_way() {
+        let red = Hsl::new(350f64, 100, 50);
+        let orange = Hsl::new(30f64, 50, 30);
+        assert_eq!(red.mix(orange, 0.5f64), Hsl::new(10f64, 75, 40));
+        assert_eq!(Hsl::new(0f64, 100, 50).to_rgb(), [1f64, 0f64, 0f64]);
+        assert_eq!(Hsl::new(120f64, 0, 100).to_rgb(), [1f64, 1f64, 1f64]);
+    }
+}
@@ -0,0 +1,289 @@
+//! Fractal trees in 3D space.
+//!
+//! Branches grow out of their parent in a cone around the parent's direction.
+//! Consecutive branches are rotated around the parent by the golden angle
+//! (phyllotaxis), so they spread evenly in all directions. Every branch is
+//! a cylinder with a radius that tapers by level. The trees can be exported
+//! as meshes (see [`Mesh`]) or projected onto a plane for previews.
+//!
+//! The y axis points upwards, the same seed always results in the same tree.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use rand::Rng;
+use rand_pcg::Pcg64;
+use rand_seeder::Seeder;
+
+use crate::{Line, Tree, TreeParameters, Vector2d};
+
+mod mesh;
+
+pub use mesh::*;
+
+/// Angle between consecutive branches around their parent (degrees)
+pub const GOLDEN_ANGLE: f64 = 137.50776405003785f64;
+
+/// Radius of the trunk relative to its length
+const TRUNK_THICKNESS: f64 = 0.08f64;
+
+/// Factor by which every level's branches are thinner than their parent
+const RADIUS_TAPER: f64 = 0.7f64;
+
+/// Implements a vector in 3D space.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vector3d {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3d {
+    pub const X: Vector3d = Vector3d::new(1f64, 0f64, 0f64);
+    pub const Y: Vector3d = Vector3d::new(0f64, 1f64, 0f64);
+    pub const Z: Vector3d = Vector3d::new(0f64, 0f64, 1f64);
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn dot(self, rhs: Vector3d) -> f64 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn cross(self, rhs: Vector3d) -> Vector3d {
+        Vector3d {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        }
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Vector with the same direction and a length of 1, unchanged if its
+    /// length is zero.
+    pub fn normalized(self) -> Vector3d {
+        let length = self.length();
+        if length == 0f64 {
+            return self;
+        }
+
+        self * (1f64 / length)
+    }
+
+    /// Rotates the vector around an axis (Rodrigues' rotation formula).
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - Axis of the rotation, does not need to be normalized.
+    /// * `angle` - Angle in radians, counterclockwise when the axis points
+    ///   towards the viewer.
+    pub fn rotate_around(self, axis: Vector3d, angle: f64) -> Vector3d {
+        let axis = axis.normalized();
+        let (sin, cos) = angle.sin_cos();
+
+        self * cos + axis.cross(self) * sin + axis * (axis.dot(self) * (1f64 - cos))
+    }
+
+    /// Any vector of length 1 that is perpendicular to this one.
+    pub fn perpendicular(self) -> Vector3d {
+        let helper = if self.normalized().y.abs() < 0.9f64 {
+            Vector3d::Y
+        } else {
+            Vector3d::X
+        };
+
+        self.cross(helper).normalized()
+    }
+}
+
+impl Add for Vector3d {
+    type Output = Vector3d;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vector3d::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Vector3d {
+    type Output = Vector3d;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector3d::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<f64> for Vector3d {
+    type Output = Vector3d;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Vector3d::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Neg for Vector3d {
+    type Output = Vector3d;
+
+    fn neg(self) -> Self::Output {
+        self * -1f64
+    }
+}
+
+/// A branch of a 3D tree, a cylinder whose radius tapers from start to end.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Branch3d {
+    pub start: Vector3d,
+    pub end: Vector3d,
+    pub start_radius: f64,
+    pub end_radius: f64,
+}
+
+/// Implements a fractal tree in 3D space.
+///
+/// For every level, `branches` contains one entry with the branches of the
+/// level.
+pub struct Tree3d {
+    pub branches: Vec<Vec<Branch3d>>,
+}
+
+impl Tree3d {
+    /// Projects the tree orthographically onto a plane.
+    ///
+    /// The result can be rendered like any 2D tree, e.g. as SVG preview.
+    ///
+    /// # Arguments
+    ///
+    /// * `azimuth` - Rotation of the tree around its trunk (degrees).
+    /// * `elevation` - Height of the viewer, 0 looks at the tree from the
+    ///   side, 90 from above (degrees).
+    pub fn project(&self, azimuth: f64, elevation: f64) -> Tree {
+        let azimuth = azimuth.to_radians();
+        let elevation = elevation.to_radians();
+        let project = |point: Vector3d| {
+            let point = point
+                .rotate_around(Vector3d::Y, azimuth)
+                .rotate_around(Vector3d::X, elevation);
+
+            // y grows downwards in 2D images
+            Vector2d {
+                x: point.x,
+                y: -point.y,
+            }
+        };
+
+        let lines = self
+            .branches
+            .iter()
+            .map(|branches| {
+                branches
+                    .iter()
+                    .map(|branch| Line {
+                        start: project(branch.start),
+                        end: project(branch.end),
+                    })
+                    .collect()
+            })
+            .collect::<Vec<Vec<Line>>>();
+
+        Tree::from(lines)
+    }
+}
+
+/// A branch that still has to be generated.
+struct Bud {
+    start: Vector3d,
+    /// Direction of the branch, length 1
+    direction: Vector3d,
+    /// Vector perpendicular to `direction`, the reference for the rotation
+    /// of the branches growing out of this one
+    side: Vector3d,
+    /// Rotation of the next branch around this one (radians)
+    azimuth: f64,
+    length: f64,
+    level: usize,
+}
+
+/// Generates a fractal tree in 3D space.
+///
+/// Uses the same parameters as the 2D [`crate::tree`]: `branch_angle` is
+/// the angle between a branch and its parent, `gravity` bends branches
+/// downwards and `wind` along the x axis. `left_angle` and `right_angle`
+/// are ignored because branches spread around their parent.
+///
+/// # Arguments:
+///
+/// * `seed` - Seed for the random number generator.
+/// * `levels` - Number of levels of the tree.
+/// * `trunk_length` - Length of the trunk of the tree.
+/// * `parameters` - Parameters influencing the visual appearance of the tree.
+pub fn tree<S: AsRef<str>>(
+    seed: S,
+    levels: usize,
+    trunk_length: f64,
+    parameters: &TreeParameters,
+) -> Tree3d {
+    // Generate a seeded random number generator
+    let mut rng: Pcg64 = Seeder::from(seed.as_ref()).make_rng();
+
+    let branches = parameters.branches.max(2);
+    let radius = |level: usize| trunk_length * TRUNK_THICKNESS * RADIUS_TAPER.powi(level as i32);
+    let mut tree = Tree3d {
+        branches: (0..levels).map(|_| Vec::new()).collect(),
+    };
+
+    let mut stack = vec![Bud {
+        start: Vector3d::default(),
+        direction: Vector3d::Y,
+        side: Vector3d::X,
+        azimuth: 0f64,
+        length: trunk_length,
+        level: 0,
+    }];
+    while let Some(bud) = stack.pop() {
+        if bud.level >= levels {
+            continue;
+        }
+
+        let end = bud.start + bud.direction * bud.length;
+        tree.branches[bud.level].push(Branch3d {
+            start: bud.start,
+            end,
+            start_radius: radius(bud.level),
+            end_radius: radius(bud.level + 1),
+        });
+
+        // If we did not yet reach the maximum level, create new branches
+        let level = bud.level + 1;
+        if level >= levels {
+            continue;
+        }
+
+        // Add a little bit of randomness to the branches' angle and length
+        let angle_change = (rng.gen_range(-0.5f64..0.5f64) * parameters.angle_jitter).to_radians();
+        let length_change = rng.gen_range(-0.5f64..0.5f64) * parameters.length_jitter;
+        let tilt = parameters.branch_angle.to_radians() + angle_change;
+        let length = bud.length * parameters.shortening + length_change;
+
+        // Push in reverse order so that the first branch is generated first
+        for index in (0..branches).rev() {
+            let azimuth = bud.azimuth + (index + 1) as f64 * GOLDEN_ANGLE.to_radians();
+            let axis = bud.side.rotate_around(bud.direction, azimuth);
+            let bias = Vector3d::new(parameters.wind, -parameters.gravity, 0f64);
+            let direction = (bud.direction.rotate_around(axis, tilt) + bias).normalized();
+
+            stack.push(Bud {
+                start: end,
+                direction,
+                // Make the side perpendicular again after applying the bias
+                side: direction.cross(axis).cross(direction).normalized(),
+                azimuth,
+                length,
+                level,
+            });
+        }
+    }
+
+    tree
+}
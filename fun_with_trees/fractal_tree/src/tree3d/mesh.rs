@@ -0,0 +1,312 @@
+use std::{
+    f64::consts::PI,
+    fmt,
+    io::{self, Write},
+};
+
+use super::*;
+use crate::{Hsl, LineSetting, Palette};
+
+/// Hue of all branches (HSL color mode) without palette, same as in 2D
+const HUE: f64 = 90f64;
+
+/// Magic number at the beginning of binary glTF files ("glTF")
+const GLB_MAGIC: u32 = 0x4654_6c67u32;
+
+/// Type of the JSON chunk of binary glTF files ("JSON")
+const GLB_JSON_CHUNK: u32 = 0x4e4f_534au32;
+
+/// Type of the binary chunk of binary glTF files ("BIN\0")
+const GLB_BINARY_CHUNK: u32 = 0x004e_4942u32;
+
+/// Largest number of vertices of a mesh, indices are 32 bit
+pub const MAX_VERTICES: usize = u32::MAX as usize;
+
+/// Error when building a mesh
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    /// The mesh would have more than [`MAX_VERTICES`] vertices
+    TooManyVertices,
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::TooManyVertices => write!(
+                f,
+                "mesh has more than {MAX_VERTICES} vertices, reduce levels, branches or segments"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// Triangle mesh with a color per vertex.
+#[derive(Debug, Default, Clone)]
+pub struct Mesh {
+    pub positions: Vec<Vector3d>,
+    /// Normals of the vertices, length 1
+    pub normals: Vec<Vector3d>,
+    /// Colors of the vertices (red, green, blue in sRGB, between 0 and 1)
+    pub colors: Vec<[f64; 3]>,
+    /// Three indices into the vertices per triangle, counterclockwise when
+    /// looking at the front
+    pub indices: Vec<u32>,
+}
+
+impl Tree3d {
+    /// Builds a mesh with a (tapering) cylinder per branch.
+    ///
+    /// Cylinders are open, the ends are hidden in the parent and child
+    /// branches anyway.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments` - Number of sides of the cylinders, at least 3.
+    /// * `palette` - Colors of the branches, green shades if `None`.
+    pub fn mesh(&self, segments: usize, palette: Option<&Palette>) -> Result<Mesh, MeshError> {
+        let segments = segments.max(3);
+        let branch_count: usize = self.branches.iter().map(Vec::len).sum();
+        let vertex_count = branch_count
+            .checked_mul(segments)
+            .and_then(|count| count.checked_mul(2))
+            .filter(|count| *count <= MAX_VERTICES)
+            .ok_or(MeshError::TooManyVertices)?;
+        let mut mesh = Mesh {
+            positions: Vec::with_capacity(vertex_count),
+            normals: Vec::with_capacity(vertex_count),
+            colors: Vec::with_capacity(vertex_count),
+            indices: Vec::with_capacity(vertex_count * 3),
+        };
+
+        let max_level = self.branches.len().saturating_sub(1);
+        for (level, branches) in self.branches.iter().enumerate() {
+            let color = match palette {
+                Some(palette) => palette.color(level, max_level),
+                None => Hsl::new(HUE, 100u8, LineSetting::new(level, max_level).lightness),
+            }
+            .to_rgb();
+
+            for branch in branches.iter() {
+                // (u, v, direction) is a right-handed coordinate system
+                let direction = (branch.end - branch.start).normalized();
+                let u = direction.perpendicular();
+                let v = direction.cross(u);
+
+                // Fits, the number of vertices was checked above
+                let first = mesh.positions.len() as u32;
+                for segment in 0..segments {
+                    let angle = 2f64 * PI * segment as f64 / segments as f64;
+                    let normal = u * angle.cos() + v * angle.sin();
+                    mesh.positions
+                        .push(branch.start + normal * branch.start_radius);
+                    mesh.positions.push(branch.end + normal * branch.end_radius);
+                    mesh.normals.extend([normal, normal]);
+                    mesh.colors.extend([color, color]);
+                }
+
+                for segment in 0..segments {
+                    let start = first + 2 * segment as u32;
+                    let next_start = first + 2 * ((segment + 1) % segments) as u32;
+                    mesh.indices.extend([start, next_start, next_start + 1]);
+                    mesh.indices.extend([start, next_start + 1, start + 1]);
+                }
+            }
+        }
+
+        Ok(mesh)
+    }
+}
+
+impl Mesh {
+    /// Writes the mesh as Wavefront OBJ file.
+    ///
+    /// Vertex colors are appended to the vertex positions (`v x y z r g b`),
+    /// an extension understood by most tools and ignored by the others.
+    pub fn write_obj<W: Write>(&self, mut out: W) -> io::Result<W> {
+        for (position, [r, g, b]) in self.positions.iter().zip(self.colors.iter()) {
+            writeln!(
+                out,
+                "v {:.4} {:.4} {:.4} {r:.4} {g:.4} {b:.4}",
+                position.x, position.y, position.z
+            )?;
+        }
+        for normal in self.normals.iter() {
+            writeln!(out, "vn {:.4} {:.4} {:.4}", normal.x, normal.y, normal.z)?;
+        }
+
+        // Indices are 1-based, vertex and normal indices are the same
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+            writeln!(out, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        }
+
+        Ok(out)
+    }
+
+    /// Writes the mesh as binary glTF 2.0 file (`.glb`).
+    ///
+    /// The file contains a single node with a single mesh. Colors are stored
+    /// as vertex colors, the material is not metallic and rough. Fails for
+    /// empty meshes (glTF does not allow empty buffers) and for meshes that do
+    /// not fit into a file (4 GiB).
+    pub fn write_glb<W: Write>(&self, mut out: W) -> io::Result<W> {
+        let vertex_count = self.positions.len();
+        if vertex_count == 0 || self.indices.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "glTF files cannot contain empty meshes",
+            ));
+        }
+
+        // Binary data: positions, normals, colors, indices (all little endian)
+        let vectors_length = vertex_count * 3 * 4;
+        let indices_length = self.indices.len() * 4;
+        let mut binary = Vec::with_capacity(3 * vectors_length + indices_length);
+        let mut write_vectors = |vectors: &mut dyn Iterator<Item = [f64; 3]>| {
+            vectors
+                .flatten()
+                .for_each(|value| binary.extend((value as f32).to_le_bytes()));
+        };
+        write_vectors(&mut self.positions.iter().map(|p| [p.x, p.y, p.z]));
+        write_vectors(&mut self.normals.iter().map(|n| [n.x, n.y, n.z]));
+        // Vertex colors are linear, not sRGB
+        write_vectors(&mut self.colors.iter().map(|c| c.map(srgb_to_linear)));
+        self.indices
+            .iter()
+            .for_each(|index| binary.extend(index.to_le_bytes()));
+        binary.resize(binary.len().next_multiple_of(4), 0u8);
+
+        // Accessors need the bounds of the positions
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for position in self.positions.iter() {
+            for (axis, value) in [position.x, position.y, position.z].into_iter().enumerate() {
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+
+        let json = format!(
+            concat!(
+                r#"{{"asset":{{"version":"2.0","generator":"fractal_tree"}},"#,
+                r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+                r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1,"COLOR_0":2}},"indices":3,"material":0,"mode":4}}]}}],"#,
+                r#""materials":[{{"pbrMetallicRoughness":{{"baseColorFactor":[1,1,1,1],"metallicFactor":0,"roughnessFactor":1}}}}],"#,
+                r#""buffers":[{{"byteLength":{buffer_length}}}],"#,
+                r#""bufferViews":["#,
+                r#"{{"buffer":0,"byteOffset":0,"byteLength":{vectors_length},"target":34962}},"#,
+                r#"{{"buffer":0,"byteOffset":{normals_offset},"byteLength":{vectors_length},"target":34962}},"#,
+                r#"{{"buffer":0,"byteOffset":{colors_offset},"byteLength":{vectors_length},"target":34962}},"#,
+                r#"{{"buffer":0,"byteOffset":{indices_offset},"byteLength":{indices_length},"target":34963}}],"#,
+                r#""accessors":["#,
+                r#"{{"bufferView":0,"componentType":5126,"count":{vertex_count},"type":"VEC3","min":[{min_x},{min_y},{min_z}],"max":[{max_x},{max_y},{max_z}]}},"#,
+                r#"{{"bufferView":1,"componentType":5126,"count":{vertex_count},"type":"VEC3"}},"#,
+                r#"{{"bufferView":2,"componentType":5126,"count":{vertex_count},"type":"VEC3"}},"#,
+                r#"{{"bufferView":3,"componentType":5125,"count":{index_count},"type":"SCALAR"}}]}}"#
+            ),
+            buffer_length = binary.len(),
+            vectors_length = vectors_length,
+            normals_offset = vectors_length,
+            colors_offset = 2 * vectors_length,
+            indices_offset = 3 * vectors_length,
+            indices_length = indices_length,
+            vertex_count = vertex_count,
+            index_count = self.indices.len(),
+            // Same precision as the binary data
+            min_x = min[0] as f32,
+            min_y = min[1] as f32,
+            min_z = min[2] as f32,
+            max_x = max[0] as f32,
+            max_y = max[1] as f32,
+            max_z = max[2] as f32,
+        );
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        // Header and chunks, each chunk starts with its length and type
+        let total_length = u32::try_from(12 + 8 + json.len() + 8 + binary.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "mesh is too large for a binary glTF file",
+            )
+        })?;
+        for value in [GLB_MAGIC, 2u32, total_length] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        for (chunk_type, chunk) in [(GLB_JSON_CHUNK, &json), (GLB_BINARY_CHUNK, &binary)] {
+            out.write_all(&(chunk.len() as u32).to_le_bytes())?;
+            out.write_all(&chunk_type.to_le_bytes())?;
+            out.write_all(chunk)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Converts a color component from sRGB to linear RGB.
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045f64 {
+        value / 12.92f64
+    } else {
+        ((value + 0.055f64) / 1.055f64).powf(2.4f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TreeParameters;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn glb_chunks_add_up() {
+        let mesh = tree("glb", 3, 100f64, &TreeParameters::default())
+            .mesh(4, None)
+            .unwrap();
+        assert_eq!(mesh.positions.len(), 7 * 4 * 2);
+        assert_eq!(mesh.indices.len(), 7 * 4 * 6);
+
+        let glb = mesh.write_glb(Vec::new()).unwrap();
+        assert_eq!(read_u32(&glb, 0), GLB_MAGIC);
+        assert_eq!(read_u32(&glb, 8) as usize, glb.len());
+        let json_length = read_u32(&glb, 12) as usize;
+        assert_eq!(read_u32(&glb, 16), GLB_JSON_CHUNK);
+        let binary_length = read_u32(&glb, 20 + json_length) as usize;
+        assert_eq!(read_u32(&glb, 24 + json_length), GLB_BINARY_CHUNK);
+        assert_eq!(28 + json_length + binary_length, glb.len());
+        assert_eq!(binary_length, (3 * 3 * 56 + 168) * 4);
+    }
+
+    #[test]
+    fn empty_meshes_are_rejected() {
+        let mesh = tree("empty", 0, 100f64, &TreeParameters::default())
+            .mesh(8, None)
+            .unwrap();
+        assert!(mesh.positions.is_empty());
+        assert_eq!(
+            mesh.write_glb(Vec::new()).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        // OBJ files may be empty
+        assert!(mesh.write_obj(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn indices_must_fit_into_32_bits() {
+        let tree = tree("large", 2, 100f64, &TreeParameters::default());
+        assert_eq!(
+            tree.mesh(MAX_VERTICES / 4, None).unwrap_err(),
+            MeshError::TooManyVertices
+        );
+        assert_eq!(
+            tree.mesh(usize::MAX, None).unwrap_err(),
+            MeshError::TooManyVertices
+        );
+    }
+}
@@ -1,6 +1,7 @@
-use std::{fs, io::Write};
-use anyhow::Result;
+use std::{fs, io::{self, Write}, path::Path};
+use anyhow::{bail, Result};
 use clap::Parser;
+use fractal_tree::{lsystem::Preset, tree3d, AnimationOptions, Easing, Scene, Season, SvgOptions, TreeParameters};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -9,10 +10,19 @@ struct Args {
    #[arg(short, long, default_value = "Hello World")]
    seed: String,
 
-   /// Name of target file
+   /// Name of target file, `.png` files are rendered as PNG, `.gif` files as
+   /// animated GIF, `.obj` and `.glb` files as 3D meshes, all others as SVG
    #[arg(short, long, default_value = "tree.svg")]
    target_file_name: String,
 
+   /// Width of PNG and GIF images in pixels, width of scenes
+   #[arg(long, default_value_t = 1024u32)]
+   width: u32,
+
+   /// Height of PNG and GIF images in pixels
+   #[arg(long, default_value_t = 1024u32)]
+   height: u32,
+
    /// Depth of the tree
    #[arg(short = 'd', long, default_value_t = 13usize)]
    tree_depth: usize,
@@ -20,14 +30,189 @@ struct Args {
    /// Trunk length
    #[arg(short = 'l', long, default_value_t = 100f64)]
    trunk_length: f64,
+
+   /// Factor by which every level's branches are shorter than their parent
+   #[arg(long, default_value_t = TreeParameters::default().shortening)]
+   shortening: f64,
+
+   /// Angle between a branch and its parent (degrees)
+   #[arg(long, default_value_t = TreeParameters::default().branch_angle)]
+   branch_angle: f64,
+
+   /// Angle of the leftmost branch (degrees), overrides the branch angle
+   #[arg(long)]
+   left_angle: Option<f64>,
+
+   /// Angle of the rightmost branch (degrees), overrides the branch angle
+   #[arg(long)]
+   right_angle: Option<f64>,
+
+   /// Maximum random change of the branches' angle (degrees)
+   #[arg(long, default_value_t = TreeParameters::default().angle_jitter)]
+   angle_jitter: f64,
+
+   /// Maximum random change of the branches' length
+   #[arg(long, default_value_t = TreeParameters::default().length_jitter)]
+   length_jitter: f64,
+
+   /// Number of branches growing out of every node
+   #[arg(short, long, default_value_t = TreeParameters::default().branches)]
+   branches: usize,
+
+   /// Bends branches downwards (positive) or upwards (negative)
+   #[arg(long, default_value_t = TreeParameters::default().gravity, allow_negative_numbers = true)]
+   gravity: f64,
+
+   /// Bends branches to the right (positive) or to the left (negative)
+   #[arg(long, default_value_t = TreeParameters::default().wind, allow_negative_numbers = true)]
+   wind: f64,
+
+   /// Colors and foliage of the tree (spring, autumn, winter, cherry-blossom)
+   #[arg(long)]
+   season: Option<Season>,
+
+   /// Animate the growth of the tree in SVG output (GIF output is always animated)
+   #[arg(long)]
+   animate: bool,
+
+   /// Duration of the animation in seconds
+   #[arg(long, default_value_t = AnimationOptions::default().duration)]
+   duration: f64,
+
+   /// Progression of the growth of every level (linear, ease-in, ease-out, ease-in-out)
+   #[arg(long, default_value_t = Easing::default())]
+   easing: Easing,
+
+   /// Generate a 3D tree, images show it from the side (implied for `.obj` and `.glb` files)
+   #[arg(long = "3d")]
+   three_d: bool,
+
+   /// Rotation of 3D trees around their trunk in images (degrees)
+   #[arg(long, default_value_t = 0f64, allow_negative_numbers = true)]
+   azimuth: f64,
+
+   /// Height of the viewer of 3D trees in images, 90 looks from above (degrees)
+   #[arg(long, default_value_t = 0f64, allow_negative_numbers = true)]
+   elevation: f64,
+
+   /// Number of sides of the branches of 3D meshes
+   #[arg(long, default_value_t = 8usize)]
+   segments: usize,
+
+   /// Render a forest with the given number of trees (SVG only)
+   #[arg(long)]
+   trees: Option<usize>,
+
+   /// Draw an L-system instead of the fractal tree (plant, koch, sierpinski, dragon)
+   #[arg(long)]
+   lsystem: Option<Preset>,
+
+   /// Number of iterations of the L-system, a detailed result if not given
+   #[arg(long)]
+   iterations: Option<usize>,
+}
+
+impl Args {
+    fn tree_parameters(&self) -> TreeParameters {
+        TreeParameters {
+            shortening: self.shortening,
+            branch_angle: self.branch_angle,
+            left_angle: self.left_angle,
+            right_angle: self.right_angle,
+            angle_jitter: self.angle_jitter,
+            length_jitter: self.length_jitter,
+            branches: self.branches,
+            gravity: self.gravity,
+            wind: self.wind,
+        }
+    }
+
+    fn animation_options(&self) -> AnimationOptions {
+        AnimationOptions {
+            duration: self.duration,
+            easing: self.easing,
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let buffer = fractal_tree::tree_svg(args.seed, args.tree_depth, args.trunk_length);
+    let parameters = args.tree_parameters();
+    let extension = Path::new(&args.target_file_name).extension();
+    let is_png = extension.is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    let is_gif = extension.is_some_and(|e| e.eq_ignore_ascii_case("gif"));
+    let is_obj = extension.is_some_and(|e| e.eq_ignore_ascii_case("obj"));
+    let is_glb = extension.is_some_and(|e| e.eq_ignore_ascii_case("glb"));
+    let palette = args.season.map(Season::palette);
+
+    if args.lsystem.is_some() && (args.trees.is_some() || args.three_d || is_obj || is_glb) {
+        bail!("L-systems can neither be rendered as scenes nor in 3D");
+    }
+
+    if let Some(trees) = args.trees {
+        if is_png || is_gif || is_obj || is_glb {
+            bail!("scenes can only be rendered as SVG");
+        }
+
+        let mut scene = Scene::forest(&args.seed, trees, args.width as f64, args.tree_depth, args.trunk_length, &parameters);
+        if let Some(season) = args.season {
+            for (index, scene_tree) in scene.trees.iter_mut().enumerate() {
+                scene_tree.tree.grow_foliage(format!("{} {index}", args.seed), &season.foliage());
+            }
+        }
+        let svg_options = SvgOptions {
+            palette,
+            ..Default::default()
+        };
+        let mut file = io::BufWriter::new(fs::File::create(&args.target_file_name)?);
+        fractal_tree::write_scene_svg(&scene, &svg_options, &mut file)?;
+        file.flush()?;
+        return Ok(());
+    }
+
+    if is_obj || is_glb {
+        let tree = tree3d::tree(&args.seed, args.tree_depth, args.trunk_length, &parameters);
+        let mesh = tree.mesh(args.segments, palette.as_ref())?;
+        let mut file = io::BufWriter::new(fs::File::create(&args.target_file_name)?);
+        if is_obj {
+            mesh.write_obj(&mut file)?;
+        } else {
+            mesh.write_glb(&mut file)?;
+        }
+        file.flush()?;
+        return Ok(());
+    }
+
+    let mut tree = if let Some(preset) = args.lsystem {
+        let iterations = args.iterations.unwrap_or(preset.default_iterations());
+        preset.lsystem().tree(&args.seed, iterations)?
+    } else if args.three_d {
+        tree3d::tree(&args.seed, args.tree_depth, args.trunk_length, &parameters)
+            .project(args.azimuth, args.elevation)
+    } else {
+        fractal_tree::tree_parallel(&args.seed, args.tree_depth, args.trunk_length, &parameters)?
+    };
+    if let Some(season) = args.season {
+        tree.grow_foliage(&args.seed, &season.foliage());
+    }
+    let svg_options = SvgOptions {
+        palette,
+        ..Default::default()
+    };
+
     let mut file =
-        fs::File::create(args.target_file_name)?;
-    file.write_all(buffer.as_bytes())?;
+        io::BufWriter::new(fs::File::create(&args.target_file_name)?);
+    if is_png {
+        file.write_all(&fractal_tree::render_png(&tree, args.width, args.height, palette.as_ref())?)?;
+    } else if is_gif {
+        let gif = fractal_tree::render_gif(&tree, args.width, args.height, palette.as_ref(), &args.animation_options())?;
+        file.write_all(&gif)?;
+    } else if args.animate {
+        fractal_tree::write_animated_svg(&tree, &svg_options, &args.animation_options(), &mut file)?;
+    } else {
+        fractal_tree::write_svg(&tree, &svg_options, &mut file)?;
+    }
+    file.flush()?;
     Ok(())
 }
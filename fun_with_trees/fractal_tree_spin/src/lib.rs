@@ -1,4 +1,5 @@
 use anyhow::Result;
+use fractal_tree::{Scene, Season, SvgOptions};
 use spin_sdk::{
     http::{Request, Response},
     http_component,
@@ -6,14 +7,163 @@ use spin_sdk::{
 
 mod parameters;
 
+/// Images only depend on the parameters, so clients and proxies may cache them
+const CACHE_CONTROL: &str = "public, max-age=86400";
+
 #[http_component]
 fn fractal_tree_spin(_req: Request) -> Result<Response> {
-    let params = parameters::extract_parameters(&_req);
+    let scene = _req.uri().path().trim_end_matches('/') == "/scene";
+    let params = match parameters::extract_parameters(&_req, scene) {
+        Ok(params) => params,
+        Err(issues) => return bad_request(&issues),
+    };
+
+    // Clients asking for PNG or GIF get a bitmap, all others (and scenes) SVG
+    let accept = _req
+        .headers()
+        .get(http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let content_type = if scene {
+        "image/svg+xml"
+    } else if accept.contains("image/png") {
+        "image/png"
+    } else if accept.contains("image/gif") {
+        "image/gif"
+    } else {
+        "image/svg+xml"
+    };
+    if content_type == "image/gif" {
+        if let Err(issue) = params.check_gif_size() {
+            return bad_request(&[issue]);
+        }
+    }
 
-    let buffer = fractal_tree::tree_svg(params.seed, params.depth, params.trunk_length);
+    // The same parameters always result in the same image
+    let etag = etag(&format!(
+        "{}?{} {content_type}",
+        _req.uri().path(),
+        params.normalized()
+    ));
+    let not_modified = _req
+        .headers()
+        .get(http::header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        });
+    if not_modified {
+        return Ok(http::Response::builder()
+            .status(304)
+            .header("etag", &etag)
+            .header("cache-control", CACHE_CONTROL)
+            .header("vary", "accept")
+            .body(None)?);
+    }
+
+    let buffer = if scene {
+        render_scene(&params)?
+    } else {
+        render_tree(&params, content_type)?
+    };
 
     Ok(http::Response::builder()
         .status(200)
-        .header("content-type", "image/svg+xml")
+        .header("content-type", content_type)
+        .header("etag", &etag)
+        .header("cache-control", CACHE_CONTROL)
+        .header("vary", "accept")
         .body(Some(buffer.into()))?)
 }
+
+/// Responds with a JSON document listing all problems with the parameters.
+fn bad_request(issues: &[parameters::ParameterIssue]) -> Result<Response> {
+    let body = serde_json::json!({
+        "error": "invalid query parameters",
+        "issues": issues,
+    });
+
+    Ok(http::Response::builder()
+        .status(400)
+        .header("content-type", "application/json")
+        .body(Some(serde_json::to_vec(&body)?.into()))?)
+}
+
+/// Strong entity tag (FNV-1a hash) of a normalized request.
+fn etag(normalized: &str) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325u64;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3u64;
+
+    // Include the version, rendering might change with it
+    let hash = env!("CARGO_PKG_VERSION")
+        .bytes()
+        .chain(normalized.bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+    format!("\"{hash:016x}\"")
+}
+
+/// Renders a single tree in the requested format.
+fn render_tree(params: &parameters::Parameters, content_type: &str) -> Result<Vec<u8>> {
+    let mut tree = fractal_tree::tree(
+        &params.seed,
+        params.depth,
+        params.trunk_length,
+        &params.tree,
+    );
+    if let Some(season) = params.season {
+        tree.grow_foliage(&params.seed, &season.foliage());
+    }
+    let palette = params.season.map(Season::palette);
+
+    Ok(match content_type {
+        "image/png" => {
+            fractal_tree::render_png(&tree, params.width, params.height, palette.as_ref())?
+        }
+        "image/gif" => fractal_tree::render_gif(
+            &tree,
+            params.width,
+            params.height,
+            palette.as_ref(),
+            &params.animation,
+        )?,
+        _ => {
+            let options = SvgOptions {
+                palette,
+                ..Default::default()
+            };
+            if params.animate {
+                fractal_tree::write_animated_svg(&tree, &options, &params.animation, Vec::new())?
+            } else {
+                fractal_tree::write_svg(&tree, &options, Vec::new())?
+            }
+        }
+    })
+}
+
+/// Renders a forest as SVG, `width` is the width of the scene.
+fn render_scene(params: &parameters::Parameters) -> Result<Vec<u8>> {
+    let mut scene = Scene::forest(
+        &params.seed,
+        params.trees,
+        params.width as f64,
+        params.depth,
+        params.trunk_length,
+        &params.tree,
+    );
+    if let Some(season) = params.season {
+        for (index, scene_tree) in scene.trees.iter_mut().enumerate() {
+            scene_tree
+                .tree
+                .grow_foliage(format!("{} {index}", params.seed), &season.foliage());
+        }
+    }
+    let options = SvgOptions {
+        palette: params.season.map(Season::palette),
+        ..Default::default()
+    };
+
+    Ok(fractal_tree::write_scene_svg(&scene, &options, Vec::new())?)
+}
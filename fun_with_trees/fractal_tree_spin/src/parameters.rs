@@ -1,11 +1,70 @@
-use spin_sdk::{
-    http::Request,
-};
+use std::{fmt::Display, ops::RangeInclusive, str::FromStr};
+
+use fractal_tree::{AnimationOptions, Season, TreeParameters};
+use serde::Serialize;
+use spin_sdk::http::Request;
+
+/// Longest seed in characters
+const MAX_SEED_LENGTH: usize = 200usize;
+
+/// Allowed depths of trees
+const DEPTH: RangeInclusive<usize> = 1usize..=18usize;
+
+/// Allowed trunk lengths
+const TRUNK_LENGTH: RangeInclusive<f64> = 1f64..=1000f64;
+
+/// Allowed shortening factors
+const SHORTENING: RangeInclusive<f64> = 0.1f64..=1f64;
+
+/// Allowed branch angles (degrees)
+const ANGLE: RangeInclusive<f64> = -180f64..=180f64;
+
+/// Allowed jitter of angles (degrees)
+const ANGLE_JITTER: RangeInclusive<f64> = 0f64..=360f64;
+
+/// Allowed jitter of lengths
+const LENGTH_JITTER: RangeInclusive<f64> = 0f64..=1000f64;
+
+/// Allowed number of branches per node
+const BRANCHES: RangeInclusive<usize> = 2usize..=8usize;
+
+/// Allowed strength of gravity and wind
+const BIAS: RangeInclusive<f64> = -1f64..=1f64;
+
+/// Allowed width and height of PNG and GIF images (pixels) and scenes
+const IMAGE_SIZE: RangeInclusive<u32> = 1u32..=4096u32;
+
+/// Allowed duration of animations (seconds)
+const DURATION: RangeInclusive<f64> = 0f64..=10f64;
+
+/// Largest number of pixels of all frames of a GIF
+/// (every frame is rasterized and quantized on its own)
+const MAX_GIF_PIXELS: u64 = 1u64 << 26;
+
+/// Allowed number of trees in scenes
+const TREES: RangeInclusive<usize> = 1usize..=50usize;
+
+/// Largest number of lines of all trees in an image
+/// (trees with many branches per node grow quickly)
+const MAX_LINES: usize = 1usize << 18;
 
 pub struct Parameters {
     pub seed: String,
     pub depth: usize,
     pub trunk_length: f64,
+    pub tree: TreeParameters,
+    /// Width of PNG images in pixels
+    pub width: u32,
+    /// Height of PNG images in pixels
+    pub height: u32,
+    /// Animate the growth of the tree in SVG output
+    pub animate: bool,
+    /// Timing of animated SVG and GIF output
+    pub animation: AnimationOptions,
+    /// Colors and foliage of the tree, green without leaves if `None`
+    pub season: Option<Season>,
+    /// Number of trees in scenes
+    pub trees: usize,
 }
 
 impl Default for Parameters {
@@ -14,26 +73,255 @@ impl Default for Parameters {
             seed: "Hello WASM".to_string(),
             depth: 13usize,
             trunk_length: 100f64,
+            tree: TreeParameters::default(),
+            width: 1024u32,
+            height: 1024u32,
+            animate: false,
+            animation: AnimationOptions::default(),
+            season: None,
+            trees: 12usize,
         }
     }
 }
 
-pub fn extract_parameters(req: &Request) -> Parameters {
-    let query = req.uri().query().unwrap_or("");
+/// Problem with a query parameter
+#[derive(Debug, Serialize)]
+pub struct ParameterIssue {
+    pub parameter: String,
+    pub message: String,
+}
+
+impl ParameterIssue {
+    fn new(parameter: &str, message: impl Into<String>) -> Self {
+        Self {
+            parameter: parameter.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Parses a number and checks that it is within the allowed range.
+fn parse_in_range<T>(
+    parameter: &str,
+    value: &str,
+    range: RangeInclusive<T>,
+) -> Result<T, ParameterIssue>
+where
+    T: FromStr + PartialOrd + Display,
+{
+    let number: T = value
+        .parse()
+        .map_err(|_| ParameterIssue::new(parameter, format!("'{value}' is not a valid number")))?;
+    if !range.contains(&number) {
+        return Err(ParameterIssue::new(
+            parameter,
+            format!("must be between {} and {}", range.start(), range.end()),
+        ));
+    }
+
+    Ok(number)
+}
+
+/// Extracts the parameters from the query string.
+///
+/// Keys and values are percent-decoded. All problems (unknown parameters,
+/// invalid values, values out of range) are collected, so clients can fix
+/// them at once.
+///
+/// # Arguments
+///
+/// * `req` - The request.
+/// * `scene` - Whether the request is for a scene, the depth is limited by
+///   the total number of lines of all its trees.
+pub fn extract_parameters(req: &Request, scene: bool) -> Result<Parameters, Vec<ParameterIssue>> {
+    parse_query(req.uri().query().unwrap_or(""), scene)
+}
+
+/// Parses the parameters from a (still percent-encoded) query string, see
+/// [`extract_parameters`].
+fn parse_query(query: &str, scene: bool) -> Result<Parameters, Vec<ParameterIssue>> {
     let mut params = Parameters::default();
+    let mut issues = Vec::new();
 
-    for pair in query.split('&').filter(|s| !s.is_empty()) {
-        let mut parts = pair.split('=');
-        let key = parts.next().unwrap();
-        let value = parts.next().unwrap();
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        let key = key.as_ref();
+        let value = value.as_ref();
+        let result = match key {
+            "seed" if value.is_empty() => Err(ParameterIssue::new(key, "must not be empty")),
+            "seed" if value.chars().count() > MAX_SEED_LENGTH => Err(ParameterIssue::new(
+                key,
+                format!("must not be longer than {MAX_SEED_LENGTH} characters"),
+            )),
+            "seed" => {
+                params.seed = value.to_string();
+                Ok(())
+            }
+            "depth" => parse_in_range(key, value, DEPTH).map(|v| params.depth = v),
+            "trunk" => parse_in_range(key, value, TRUNK_LENGTH).map(|v| params.trunk_length = v),
+            "shortening" => {
+                parse_in_range(key, value, SHORTENING).map(|v| params.tree.shortening = v)
+            }
+            "angle" => parse_in_range(key, value, ANGLE).map(|v| params.tree.branch_angle = v),
+            "left_angle" => {
+                parse_in_range(key, value, ANGLE).map(|v| params.tree.left_angle = Some(v))
+            }
+            "right_angle" => {
+                parse_in_range(key, value, ANGLE).map(|v| params.tree.right_angle = Some(v))
+            }
+            "angle_jitter" => {
+                parse_in_range(key, value, ANGLE_JITTER).map(|v| params.tree.angle_jitter = v)
+            }
+            "length_jitter" => {
+                parse_in_range(key, value, LENGTH_JITTER).map(|v| params.tree.length_jitter = v)
+            }
+            "branches" => parse_in_range(key, value, BRANCHES).map(|v| params.tree.branches = v),
+            "gravity" => parse_in_range(key, value, BIAS).map(|v| params.tree.gravity = v),
+            "wind" => parse_in_range(key, value, BIAS).map(|v| params.tree.wind = v),
+            "width" => parse_in_range(key, value, IMAGE_SIZE).map(|v| params.width = v),
+            "height" => parse_in_range(key, value, IMAGE_SIZE).map(|v| params.height = v),
+            // `?animate` without value turns the animation on
+            "animate" if value.is_empty() => {
+                params.animate = true;
+                Ok(())
+            }
+            "animate" => value
+                .parse()
+                .map(|v| params.animate = v)
+                .map_err(|_| ParameterIssue::new(key, "must be true or false")),
+            "duration" => {
+                parse_in_range(key, value, DURATION).map(|v| params.animation.duration = v)
+            }
+            "easing" => value
+                .parse()
+                .map(|v| params.animation.easing = v)
+                .map_err(|message: String| ParameterIssue::new(key, message)),
+            "season" => value
+                .parse()
+                .map(|v| params.season = Some(v))
+                .map_err(|message: String| ParameterIssue::new(key, message)),
+            "trees" => parse_in_range(key, value, TREES).map(|v| params.trees = v),
+            _ => Err(ParameterIssue::new(key, "unknown parameter")),
+        };
+        if let Err(issue) = result {
+            issues.push(issue);
+        }
+    }
+
+    // Only check the size of the image if depth and branches are valid
+    if issues.is_empty() {
+        let trees = if scene { params.trees } else { 1usize };
+        let too_large = params
+            .tree
+            .line_count(params.depth)
+            .and_then(|lines| lines.checked_mul(trees))
+            .is_none_or(|lines| lines > MAX_LINES);
+        if too_large {
+            issues.push(ParameterIssue::new(
+                "depth",
+                format!("results in more than {MAX_LINES} lines, reduce depth, branches or trees"),
+            ));
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(params)
+    } else {
+        Err(issues)
+    }
+}
+
+impl Parameters {
+    /// Checks that an animated GIF of the tree isn't too large to render.
+    pub fn check_gif_size(&self) -> Result<(), ParameterIssue> {
+        let frames = fractal_tree::gif_frame_count(&self.animation) as u64;
+        let pixels = u64::from(self.width) * u64::from(self.height) * frames;
+        if pixels > MAX_GIF_PIXELS {
+            return Err(ParameterIssue::new(
+                "duration",
+                format!("results in a GIF with more than {MAX_GIF_PIXELS} pixels in all frames, reduce width, height or duration"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Query string with all parameters (including defaults) in a fixed
+    /// order, equal for all requests resulting in the same image.
+    pub fn normalized(&self) -> String {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("seed", &self.seed)
+            .append_pair("depth", &self.depth.to_string())
+            .append_pair("trunk", &self.trunk_length.to_string())
+            .append_pair("shortening", &self.tree.shortening.to_string())
+            .append_pair("angle", &self.tree.branch_angle.to_string())
+            .append_pair("angle_jitter", &self.tree.angle_jitter.to_string())
+            .append_pair("length_jitter", &self.tree.length_jitter.to_string())
+            .append_pair("branches", &self.tree.branches.to_string())
+            .append_pair("gravity", &self.tree.gravity.to_string())
+            .append_pair("wind", &self.tree.wind.to_string())
+            .append_pair("width", &self.width.to_string())
+            .append_pair("height", &self.height.to_string())
+            .append_pair("animate", &self.animate.to_string())
+            .append_pair("duration", &self.animation.duration.to_string())
+            .append_pair("easing", self.animation.easing.name())
+            .append_pair("trees", &self.trees.to_string());
+        if let Some(left_angle) = self.tree.left_angle {
+            query.append_pair("left_angle", &left_angle.to_string());
+        }
+        if let Some(right_angle) = self.tree.right_angle {
+            query.append_pair("right_angle", &right_angle.to_string());
+        }
+        if let Some(season) = self.season {
+            query.append_pair("season", season.name());
+        }
+
+        query.finish()
+    }
+}
 
-        match key {
-            "seed" => params.seed = value.to_string(),
-            "depth" => params.depth = value.parse().unwrap_or(params.depth),
-            "trunk" => params.trunk_length = value.parse().unwrap_or(params.trunk_length),
-            _ => {},
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(query: &str) -> Vec<String> {
+        match parse_query(query, false) {
+            Ok(_) => Vec::new(),
+            Err(issues) => issues.into_iter().map(|issue| issue.parameter).collect(),
         }
     }
 
-    params
-}
\ No newline at end of file
+    #[test]
+    fn seeds_must_not_be_empty() {
+        assert_eq!(issues("seed"), ["seed"]);
+        assert_eq!(issues("seed="), ["seed"]);
+        assert_eq!(issues("seed=&depth=3"), ["seed"]);
+        assert_eq!(issues("seed=oak").len(), 0);
+    }
+
+    #[test]
+    fn seeds_are_percent_decoded() {
+        let params = parse_query("seed=Hello+WASM%21", false).ok().unwrap();
+        assert_eq!(params.seed, "Hello WASM!");
+    }
+
+    #[test]
+    fn default_gif_is_not_too_large() {
+        assert!(Parameters::default().check_gif_size().is_ok());
+    }
+
+    #[test]
+    fn large_gifs_are_rejected() {
+        let params = parse_query("width=4096&height=4096&duration=10", false)
+            .ok()
+            .unwrap();
+        let issue = params.check_gif_size().unwrap_err();
+        assert_eq!(issue.parameter, "duration");
+
+        // A single frame of the largest size is fine
+        let params = parse_query("width=4096&height=4096&duration=0", false)
+            .ok()
+            .unwrap();
+        assert!(params.check_gif_size().is_ok());
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use word_puzzle_generator::{
+    place_words,
+    render::{render_pdf, RenderOptions},
+    validate, Difficulty, GeneratorOptions, Puzzle, MASKED_CELL,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// Grid, word list, and answer key as text
+    Text,
+    /// Puzzle as JSON (same as the HTTP hosts return)
+    Json,
+    /// Printable PDF (written to stdout, redirect it into a file)
+    Pdf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Level {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl From<Level> for Difficulty {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Easy => Difficulty::Easy,
+            Level::Medium => Difficulty::Medium,
+            Level::Hard => Difficulty::Hard,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// File with one word per line, reads from stdin if missing or `-`
+    words: Option<PathBuf>,
+
+    /// Size of a square grid
+    #[arg(short, long, default_value_t = 12usize)]
+    size: usize,
+
+    /// Width of the grid (overrides size)
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Height of the grid (overrides size)
+    #[arg(long)]
+    height: Option<usize>,
+
+    /// Picks words from a bundled theme instead of reading them
+    #[arg(short, long)]
+    theme: Option<String>,
+
+    /// Language of the theme
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Difficulty to aim for
+    #[arg(short, long)]
+    difficulty: Option<Level>,
+
+    /// Message hidden in the unused cells
+    #[arg(long)]
+    hidden_message: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let words = match (&args.theme, &args.words) {
+        (Some(_), None) => Vec::new(),
+        (Some(_), Some(_)) => bail!("either a word file or a theme can be given, not both"),
+        (None, path) => read_words(path.as_ref())?,
+    };
+
+    let options = GeneratorOptions {
+        size: args.size,
+        width: args.width,
+        height: args.height,
+        theme: args.theme,
+        language: args.language,
+        target_difficulty: args.difficulty.map(Difficulty::from),
+        hidden_message: args.hidden_message,
+        words,
+        ..Default::default()
+    };
+
+    if let Err(errors) = validate(&options) {
+        for error in errors {
+            eprintln!("error: {error}");
+        }
+        std::process::exit(1);
+    }
+
+    let puzzle = place_words(options)?;
+    let mut stdout = io::stdout().lock();
+    match args.format {
+        Format::Text => stdout.write_all(text(&puzzle).as_bytes())?,
+        Format::Json => writeln!(stdout, "{}", serde_json::to_string_pretty(&puzzle)?)?,
+        Format::Pdf => {
+            let options = RenderOptions {
+                answer_key: true,
+                ..Default::default()
+            };
+            stdout.write_all(&render_pdf(&puzzle, &options))?
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads one word per line, empty lines and lines starting with `#` are skipped
+fn read_words(path: Option<&PathBuf>) -> Result<Vec<String>> {
+    let content = match path {
+        Some(path) if path.as_os_str() != "-" => fs::read_to_string(path)
+            .with_context(|| format!("cannot read words from {}", path.display()))?,
+        _ => {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            content
+        }
+    };
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Grid, word list, and answer key (only letters of placed words)
+fn text(puzzle: &Puzzle) -> String {
+    let answers: HashSet<(usize, usize)> =
+        puzzle.placements.iter().flat_map(|p| p.cells()).collect();
+    let grid = |answers_only: bool| -> String {
+        puzzle
+            .grid
+            .iter()
+            .enumerate()
+            .map(|(row, line)| {
+                let cells: Vec<String> = line
+                    .chars()
+                    .enumerate()
+                    .map(|(col, letter)| match letter {
+                        MASKED_CELL => " ".to_string(),
+                        _ if answers_only && !answers.contains(&(row, col)) => "·".to_string(),
+                        _ => letter.to_string(),
+                    })
+                    .collect();
+                cells.join(" ") + "\n"
+            })
+            .collect()
+    };
+
+    format!(
+        "{}\nWords: {}\n\nAnswer key:\n{}",
+        grid(false),
+        puzzle.words.join(", "),
+        grid(true)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use word_puzzle_generator::{Direction, WordPlacement};
+
+    #[test]
+    fn text_output() {
+        let puzzle = Puzzle {
+            grid: vec!["RUST".to_string(), format!("X{MASKED_CELL}AB")],
+            words: vec!["RUST".to_string()],
+            placements: vec![WordPlacement {
+                word: "RUST".to_string(),
+                row: 0,
+                col: 0,
+                direction: Direction::Horizontal,
+                reversed: false,
+            }],
+        };
+
+        assert_eq!(
+            text(&puzzle),
+            "R U S T\nX   A B\n\nWords: RUST\n\nAnswer key:\nR U S T\n·   · ·\n"
+        );
+    }
+}
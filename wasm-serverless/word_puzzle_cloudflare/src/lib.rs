@@ -1,37 +1,129 @@
-use word_puzzle_generator::{place_words, GeneratorOptions};
+use word_puzzle_generator::api::{
+    client_key, handle, puzzle_id, ApiKeys, ApiRequest, ApiResponse, Host, MemoryPuzzleStore,
+    MemoryStore, Method, PuzzleStore, RateLimitDecision, RateLimitStore, RateLimiter, StoredPuzzle,
+    TokenBucket, SHARE_TTL_SECONDS,
+};
+use word_puzzle_generator::Puzzle;
 use worker::*;
 
 mod utils;
 
+/// KV namespace binding for rate limit buckets (see `wrangler.toml`)
+const RATE_LIMIT_NAMESPACE: &str = "RATE_LIMITS";
+
+/// KV namespace binding for shared puzzles (see `wrangler.toml`)
+const PUZZLE_NAMESPACE: &str = "PUZZLES";
+
+/// Secret with comma-separated API keys, clients sending one of them in
+/// `X-Api-Key` get their own rate limit bucket
+const API_KEYS_SECRET: &str = "API_KEYS";
+
+/// Cloudflare KV does not accept shorter expiration times
+const MIN_KV_TTL: u64 = 60;
+
+/// Thin adapter, routing and error handling live in [`word_puzzle_generator::api`]
 #[event(fetch)]
-pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Response> {
+pub async fn main(mut req: Request, env: Env, _ctx: worker::Context) -> Result<Response> {
     // Optionally, get more helpful error messages written to the console in the case of a panic.
     utils::set_panic_hook();
 
-    let router = Router::new();
-    router
-        .get("/", |_, _| Response::ok("Hello from Workers!"))
-        .post_async("/generate", generate_puzzle)
-        .run(req, env)
-        .await
+    let url = req.url()?;
+    let request = ApiRequest {
+        method: Method::parse(req.method().as_ref()),
+        path: url.path().to_string(),
+        query: url.query().unwrap_or_default().to_string(),
+        accept: req.headers().get("Accept")?.unwrap_or_default(),
+        body: req.bytes().await?,
+    };
+
+    // Requests are not limited if the KV namespace is not bound
+    let cost = request.cost();
+    if let (true, Ok(kv)) = (cost > 0f64, env.kv(RATE_LIMIT_NAMESPACE)) {
+        let api_keys = env
+            .secret(API_KEYS_SECRET)
+            .map(|keys| ApiKeys::parse(&keys.to_string()))
+            .unwrap_or_default();
+        let client = client_key(
+            req.headers().get("X-Api-Key")?.as_deref(),
+            req.headers().get("CF-Connecting-IP")?.as_deref(),
+            &api_keys,
+        );
+        if let Some(rejection) = check_rate_limit(&kv, &client, cost).await.rejection() {
+            return into_response(rejection);
+        }
+    }
+
+    let host = Host::new("Hello from Workers!", Date::now().as_millis());
+    let Ok(kv) = env.kv(PUZZLE_NAMESPACE) else {
+        return into_response(handle(&request, &host));
+    };
+
+    // Same approach as for rate limits: the requested puzzle (or the one with
+    // the ID of a puzzle to share, to detect conflicts) is loaded into memory
+    // before handling the request, new puzzles are written back after
+    let puzzles = MemoryPuzzleStore::default();
+    let id = match (request.method, request.path.trim_end_matches('/')) {
+        (Method::Post, "/puzzles") => serde_json::from_slice::<Puzzle>(&request.body)
+            .ok()
+            .map(|puzzle| puzzle_id(&puzzle)),
+        (_, path) => path.strip_prefix("/puzzles/").map(str::to_string),
+    };
+    if let Some(id) = id {
+        if let Ok(Some(puzzle)) = kv.get(&id).json::<StoredPuzzle>().await {
+            let _ = puzzles.save(&id, &puzzle);
+        }
+    }
+
+    let response = handle(&request, &host.with_puzzles(&puzzles));
+    if request.method == Method::Post && response.status == 201 {
+        for (id, puzzle) in puzzles.entries() {
+            kv.put(&id, puzzle)?
+                .expiration_ttl(SHARE_TTL_SECONDS)
+                .execute()
+                .await?;
+        }
+    }
+
+    into_response(response)
 }
 
-async fn generate_puzzle(mut req: Request, _ctx: RouteContext<()>) -> Result<Response> {
-    match req.json().await {
-        Err(_) => Response::error("Bad Request", 400),
-        Ok(options) => {
-            let options: GeneratorOptions = options;
-            if options.size > 20 {
-                return Response::error("Bad Request", 400);
-            }
-
-            let puzzle = place_words(options);
-            Response::from_json(&puzzle)?.with_cors(
-                &Cors::new()
-                    .with_origins(vec!["*"])
-                    .with_allowed_headers(vec!["*"])
-                    .with_methods(vec![Method::Post]),
-            )
+/// KV is async while [`RateLimitStore`] is not, so the client's bucket is
+/// loaded into memory, checked, and written back. KV is eventually consistent,
+/// so concurrent requests in different locations may exceed the limit slightly.
+async fn check_rate_limit(kv: &kv::KvStore, client: &str, cost: f64) -> RateLimitDecision {
+    let limiter = RateLimiter::default();
+    let store = MemoryStore::default();
+    if let Ok(Some(bucket)) = kv.get(client).json::<TokenBucket>().await {
+        store.save(client, &bucket, limiter.ttl_seconds());
+    }
+
+    let decision = limiter.check(&store, client, cost, Date::now().as_millis());
+    if let (RateLimitDecision::Allowed, Some(bucket)) = (decision, store.load(client)) {
+        if let Ok(put) = kv.put(client, bucket) {
+            let _ = put
+                .expiration_ttl(limiter.ttl_seconds().max(MIN_KV_TTL))
+                .execute()
+                .await;
         }
     }
+
+    decision
+}
+
+fn into_response(response: ApiResponse) -> Result<Response> {
+    let mut headers = Headers::new();
+    for (name, value) in response.headers.iter() {
+        headers.set(name, value)?;
+    }
+
+    // Streamed chunks are generated when the runtime reads the body
+    let body = match response.stream {
+        None => Response::from_bytes(response.body)?,
+        Some(stream) => {
+            let chunks = std::iter::once(response.body).chain(stream);
+            Response::from_stream(futures_util::stream::iter(chunks.map(Ok::<_, Error>)))?
+        }
+    };
+
+    Ok(body.with_status(response.status).with_headers(headers))
 }
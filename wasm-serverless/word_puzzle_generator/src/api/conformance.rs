@@ -0,0 +1,228 @@
+//! Conformance suite for hosts of the word puzzle API.
+//!
+//! Every host must behave the same for the requests below. The suite only
+//! needs a function that sends an [`ApiRequest`] through the host and returns
+//! the [`ApiResponse`], so it runs natively against the shared core as well as
+//! against native hosts. Streamed bodies are collected before they are checked.
+//! It panics with a description of the first mismatch.
+//! Hosts must be able to share puzzles (see [`super::Host::puzzles`]) and
+//! render printable formats (`render` feature).
+
+use serde_json::Value;
+
+use super::{ApiRequest, ApiResponse, Method, CORS_HEADERS, JSON_CONTENT_TYPE};
+use crate::{ProblemDetails, NDJSON_CONTENT_TYPE};
+
+/// Single request with the expected status and content type
+struct Case {
+    name: &'static str,
+    request: ApiRequest,
+    status: u16,
+    content_type: Option<&'static str>,
+    /// Additional checks of the body
+    check: fn(&[u8]),
+}
+
+/// Small valid puzzle as returned by `POST /generate`
+const SHARED_PUZZLE: &str = r#"{"grid": ["RUST", "ABCD"], "words": ["RUST"], "placements": [{"word": "RUST", "row": 0, "col": 0, "direction": "horizontal", "reversed": false}]}"#;
+
+fn json(body: &[u8]) -> Value {
+    serde_json::from_slice(body).expect("body is not valid JSON")
+}
+
+fn post(path: &str, body: &str) -> ApiRequest {
+    ApiRequest::new(Method::Post, path).with_body(body)
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case {
+            name: "CORS preflight",
+            request: ApiRequest::new(Method::Options, "/generate"),
+            status: 200,
+            content_type: None,
+            check: |_| {},
+        },
+        Case {
+            name: "generate puzzle",
+            request: post(
+                "/generate",
+                r#"{"size": 8, "words": ["RUST", "WASM", "CLOUD"]}"#,
+            ),
+            status: 200,
+            content_type: Some(JSON_CONTENT_TYPE),
+            check: |body| {
+                let puzzle = json(body);
+                assert_eq!(puzzle["grid"].as_array().unwrap().len(), 8);
+                assert_eq!(puzzle["placements"].as_array().unwrap().len(), 3);
+            },
+        },
+        Case {
+            name: "generate puzzle from theme",
+            request: post("/generate", r#"{"size": 12, "theme": "animals"}"#),
+            status: 200,
+            content_type: Some(JSON_CONTENT_TYPE),
+            check: |body| assert!(!json(body)["words"].as_array().unwrap().is_empty()),
+        },
+        Case {
+            name: "printable puzzle",
+            request: post("/generate", r#"{"size": 8, "words": ["RUST"]}"#)
+                .with_accept("image/svg+xml")
+                .with_query("title=Conformance"),
+            status: 200,
+            content_type: Some("image/svg+xml"),
+            check: |body| assert!(std::str::from_utf8(body).unwrap().contains("Conformance")),
+        },
+        Case {
+            name: "grid too large",
+            request: post("/generate", r#"{"size": 21, "words": ["RUST"]}"#),
+            status: 400,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["errors"][0]["code"], "sizeOutOfRange"),
+        },
+        Case {
+            name: "malformed body",
+            request: post("/generate", "{"),
+            status: 400,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["title"], "Bad Request"),
+        },
+        Case {
+            name: "batch",
+            request: post(
+                "/generate/batch",
+                r#"{"puzzles": [{"size": 8, "words": ["RUST"]}, {"size": 8, "words": []}, {"size": "8"}]}"#,
+            ),
+            status: 200,
+            content_type: Some(NDJSON_CONTENT_TYPE),
+            check: |body| {
+                let lines: Vec<Value> = std::str::from_utf8(body)
+                    .unwrap()
+                    .lines()
+                    .map(|l| json(l.as_bytes()))
+                    .collect();
+                assert_eq!(lines.len(), 3);
+                assert!(lines[0]["puzzle"].is_object());
+                assert_eq!(lines[1]["error"]["status"], 400);
+                // Malformed items fail on their own
+                assert_eq!(lines[2]["error"]["title"], "Bad Request");
+            },
+        },
+        Case {
+            name: "empty batch",
+            request: post("/generate/batch", r#"{"puzzles": []}"#),
+            status: 400,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["errors"][0]["code"], "batchSizeOutOfRange"),
+        },
+        Case {
+            name: "crossword",
+            request: post(
+                "/generate/crossword",
+                r#"{"entries": [{"word": "RUST", "clue": "Language"}, {"word": "TRUST", "clue": "Belief"}]}"#,
+            ),
+            status: 200,
+            content_type: Some(JSON_CONTENT_TYPE),
+            check: |body| assert!(json(body)["grid"].is_array()),
+        },
+        Case {
+            name: "themes",
+            request: ApiRequest::new(Method::Get, "/themes"),
+            status: 200,
+            content_type: Some(JSON_CONTENT_TYPE),
+            check: |body| assert!(!json(body)["themes"].as_array().unwrap().is_empty()),
+        },
+        Case {
+            name: "OpenAPI document",
+            request: ApiRequest::new(Method::Get, "/openapi.json"),
+            status: 200,
+            content_type: Some(JSON_CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["openapi"], "3.1.0"),
+        },
+        Case {
+            name: "share puzzle",
+            request: post("/puzzles", SHARED_PUZZLE),
+            status: 201,
+            content_type: Some(JSON_CONTENT_TYPE),
+            check: |body| assert!(json(body)["url"].as_str().unwrap().starts_with("/puzzles/")),
+        },
+        Case {
+            name: "share inconsistent puzzle",
+            request: post(
+                "/puzzles",
+                r#"{"grid": ["RUST", "ABCD"], "words": ["WASM"], "placements": [{"word": "WASM", "row": 1, "col": 0, "direction": "horizontal"}]}"#,
+            ),
+            status: 400,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["errors"][0]["code"], "placementMismatch"),
+        },
+        Case {
+            name: "unknown shared puzzle",
+            request: ApiRequest::new(Method::Get, "/puzzles/00000000000"),
+            status: 404,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["status"], 404),
+        },
+        Case {
+            name: "unknown route",
+            request: ApiRequest::new(Method::Get, "/unknown"),
+            status: 404,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["status"], 404),
+        },
+        Case {
+            name: "wrong method",
+            request: ApiRequest::new(Method::Get, "/generate"),
+            status: 405,
+            content_type: Some(ProblemDetails::CONTENT_TYPE),
+            check: |body| assert_eq!(json(body)["status"], 405),
+        },
+    ]
+}
+
+/// Runs all cases against a host
+pub fn run(mut send: impl FnMut(ApiRequest) -> ApiResponse) {
+    for case in cases() {
+        let response = send(case.request).collect_stream();
+        assert_eq!(response.status, case.status, "{}: status", case.name);
+
+        let content_type = response.header("Content-Type");
+        match case.content_type {
+            // Hosts may add parameters like `; charset=utf-8`
+            Some(expected) => assert!(
+                content_type.is_some_and(|c| c.starts_with(expected)),
+                "{}: expected content type {expected}, got {content_type:?}",
+                case.name
+            ),
+            None => assert!(
+                content_type.is_none() || response.body.is_empty(),
+                "{}: unexpected body",
+                case.name
+            ),
+        }
+
+        for (name, value) in CORS_HEADERS {
+            assert_eq!(
+                response.header(name),
+                Some(value),
+                "{}: CORS header {name}",
+                case.name
+            );
+        }
+
+        (case.check)(&response.body);
+    }
+
+    // Shared puzzles can be loaded from the returned location
+    let shared = send(post("/puzzles", SHARED_PUZZLE));
+    let location = shared
+        .header("Location")
+        .expect("share puzzle: Location header");
+    let loaded = send(ApiRequest::new(Method::Get, location));
+    assert_eq!(loaded.status, 200, "load shared puzzle: status");
+    assert_eq!(
+        json(&loaded.body),
+        json(SHARED_PUZZLE.as_bytes()),
+        "load shared puzzle: body"
+    );
+}
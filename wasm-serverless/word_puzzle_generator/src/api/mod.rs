@@ -0,0 +1,523 @@
+//! Host-agnostic HTTP core shared by all hosts (Spin, Cloudflare, ...).
+//!
+//! Hosts only translate their runtime's request into an [`ApiRequest`], call
+//! [`handle`], and translate the [`ApiResponse`] back. Routing, parsing,
+//! validation, content negotiation, CORS, and error mapping happen here, so
+//! every host behaves the same. The conformance suite (`conformance` feature)
+//! checks exactly that.
+//!
+//! Everything a host provides besides the request (its greeting, the current
+//! time, storage for shared puzzles) is passed in a [`Host`].
+
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    available_themes, generate_batch_iter, generate_crossword, place_words, validate,
+    validate_batch, validate_crossword, BatchOptions, CrosswordOptions, GeneratorOptions,
+    ProblemDetails, Puzzle, NDJSON_CONTENT_TYPE,
+};
+
+#[cfg(any(test, feature = "conformance"))]
+pub mod conformance;
+#[cfg(test)]
+mod openapi;
+mod rate_limit;
+mod share;
+
+pub use rate_limit::*;
+pub use share::*;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const TEXT_CONTENT_TYPE: &str = "text/plain";
+
+/// OpenAPI document describing all routes, generated by the tests in `openapi.rs`
+pub const OPENAPI_JSON: &str = include_str!("../../openapi.json");
+
+/// CORS headers added to every response. The API is public and does not use
+/// cookies, so every origin may call it.
+pub const CORS_HEADERS: [(&str, &str); 3] = [
+    ("Access-Control-Allow-Origin", "*"),
+    ("Access-Control-Allow-Methods", "GET, POST, OPTIONS"),
+    ("Access-Control-Allow-Headers", "*"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Options,
+    Other,
+}
+
+impl Method {
+    /// Parses an HTTP method name (case-insensitive)
+    pub fn parse(method: &str) -> Self {
+        match method.to_ascii_uppercase().as_str() {
+            "GET" => Method::Get,
+            "POST" => Method::Post,
+            "OPTIONS" => Method::Options,
+            _ => Method::Other,
+        }
+    }
+}
+
+/// Runtime-independent view of an incoming request
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    pub method: Method,
+    pub path: String,
+    /// Query string without the leading `?`
+    pub query: String,
+    /// Value of the `Accept` header, empty if missing
+    pub accept: String,
+    pub body: Vec<u8>,
+}
+
+impl ApiRequest {
+    pub fn new(method: Method, path: &str) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            query: String::new(),
+            accept: String::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn with_query(mut self, query: &str) -> Self {
+        self.query = query.to_string();
+        self
+    }
+
+    pub fn with_accept(mut self, accept: &str) -> Self {
+        self.accept = accept.to_string();
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// Rest of a response body that is produced while it is sent
+pub struct BodyStream(Box<dyn Iterator<Item = Vec<u8>> + Send>);
+
+impl BodyStream {
+    pub fn new(chunks: impl Iterator<Item = Vec<u8>> + Send + 'static) -> Self {
+        Self(Box::new(chunks))
+    }
+}
+
+impl Iterator for BodyStream {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodyStream(..)")
+    }
+}
+
+/// Runtime-independent response, hosts copy status, headers, and body
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub status: u16,
+    /// All headers including `Content-Type` and CORS headers
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Chunks following `body` (e.g. NDJSON lines of a batch). Hosts that can
+    /// stream send each chunk as soon as it is available, all others call
+    /// [`ApiResponse::collect_stream`].
+    pub stream: Option<BodyStream>,
+}
+
+impl ApiResponse {
+    fn new(status: u16, content_type: Option<&str>, body: Vec<u8>) -> Self {
+        let headers = content_type
+            .map(|c| ("Content-Type", c))
+            .into_iter()
+            .chain(CORS_HEADERS)
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Self {
+            status,
+            headers,
+            body,
+            stream: None,
+        }
+    }
+
+    fn streamed(content_type: &str, stream: BodyStream) -> Self {
+        let mut response = Self::new(200, Some(content_type), Vec::new());
+        response.stream = Some(stream);
+        response
+    }
+
+    fn json(value: &impl Serialize) -> Self {
+        Self::new(200, Some(JSON_CONTENT_TYPE), to_json(value))
+    }
+
+    fn created(location: &str, value: &impl Serialize) -> Self {
+        let mut response = Self::new(201, Some(JSON_CONTENT_TYPE), to_json(value));
+        response
+            .headers
+            .push(("Location".to_string(), location.to_string()));
+        response
+    }
+
+    /// Problem details response (e.g. for errors of the host itself)
+    pub fn problem(problem: ProblemDetails) -> Self {
+        Self::new(
+            problem.status,
+            Some(ProblemDetails::CONTENT_TYPE),
+            to_json(&problem),
+        )
+    }
+
+    /// Value of a header (case-insensitive name)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Appends all chunks of the stream to the body
+    pub fn collect_stream(mut self) -> Self {
+        if let Some(stream) = self.stream.take() {
+            self.body.extend(stream.flatten());
+        }
+        self
+    }
+}
+
+fn to_json(value: &impl Serialize) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).expect("responses only contain serializable data")
+}
+
+/// What a host provides to [`handle`] besides the request
+#[derive(Clone, Copy)]
+pub struct Host<'a> {
+    /// Returned for `GET /` so that hosts can identify themselves
+    pub greeting: &'a str,
+    /// Milliseconds since the Unix epoch
+    pub now: u64,
+    /// Storage for shared puzzles, the `/puzzles` routes answer with
+    /// `503 Service Unavailable` without it
+    pub puzzles: Option<&'a dyn PuzzleStore>,
+}
+
+impl<'a> Host<'a> {
+    pub fn new(greeting: &'a str, now: u64) -> Self {
+        Self {
+            greeting,
+            now,
+            puzzles: None,
+        }
+    }
+
+    pub fn with_puzzles(mut self, puzzles: &'a dyn PuzzleStore) -> Self {
+        self.puzzles = Some(puzzles);
+        self
+    }
+}
+
+/// Handles a request
+pub fn handle(request: &ApiRequest, host: &Host) -> ApiResponse {
+    // CORS preflight for every path
+    if request.method == Method::Options {
+        return ApiResponse::new(200, None, Vec::new());
+    }
+
+    let path = request.path.trim_end_matches('/');
+    match (request.method, path) {
+        (Method::Get, "") => ApiResponse::new(
+            200,
+            Some(TEXT_CONTENT_TYPE),
+            host.greeting.as_bytes().to_vec(),
+        ),
+        (Method::Get, "/themes") => ApiResponse::json(&available_themes()),
+        (Method::Get, "/openapi.json") => ApiResponse::new(
+            200,
+            Some(JSON_CONTENT_TYPE),
+            OPENAPI_JSON.as_bytes().to_vec(),
+        ),
+        (Method::Post, "/generate") => generate_puzzle(request),
+        (Method::Post, "/generate/batch") => generate_puzzle_batch(request),
+        (Method::Post, "/generate/crossword") => generate_crossword_puzzle(request),
+        (Method::Post, "/puzzles") => store_puzzle(request, host),
+        (Method::Get, _) if path.starts_with("/puzzles/") => {
+            shared_puzzle(request, host, &path["/puzzles/".len()..])
+        }
+        (
+            _,
+            ""
+            | "/themes"
+            | "/openapi.json"
+            | "/generate"
+            | "/generate/batch"
+            | "/generate/crossword"
+            | "/puzzles",
+        ) => method_not_allowed(path),
+        (_, _) if path.starts_with("/puzzles/") => method_not_allowed(path),
+        _ => not_found(&request.path),
+    }
+}
+
+fn method_not_allowed(path: &str) -> ApiResponse {
+    ApiResponse::problem(ProblemDetails::new(
+        405,
+        "Method Not Allowed",
+        format!("{path} does not support this method"),
+    ))
+}
+
+fn not_found(path: &str) -> ApiResponse {
+    ApiResponse::problem(ProblemDetails::new(
+        404,
+        "Not Found",
+        format!("{path} does not exist"),
+    ))
+}
+
+fn no_puzzle_store() -> ApiResponse {
+    ApiResponse::problem(ProblemDetails::new(
+        503,
+        "Service Unavailable",
+        "sharing puzzles is not configured on this host",
+    ))
+}
+
+fn parse_body<T: DeserializeOwned>(request: &ApiRequest) -> Result<T, ApiResponse> {
+    serde_json::from_slice(&request.body)
+        .map_err(|e| ApiResponse::problem(ProblemDetails::bad_request(e.to_string())))
+}
+
+fn generate_puzzle(request: &ApiRequest) -> ApiResponse {
+    let options: GeneratorOptions = match parse_body(request) {
+        Ok(options) => options,
+        Err(response) => return response,
+    };
+
+    if let Err(errors) = validate(&options) {
+        return ApiResponse::problem(errors.into());
+    }
+
+    match place_words(options) {
+        Ok(puzzle) => negotiate_puzzle(request, &puzzle),
+        Err(e) => ApiResponse::problem(e.into()),
+    }
+}
+
+/// Stores a puzzle returned by `POST /generate` and answers with its share link
+fn store_puzzle(request: &ApiRequest, host: &Host) -> ApiResponse {
+    let Some(store) = host.puzzles else {
+        return no_puzzle_store();
+    };
+    let puzzle: Puzzle = match parse_body(request) {
+        Ok(puzzle) => puzzle,
+        Err(response) => return response,
+    };
+
+    if let Err(errors) = validate_puzzle(&puzzle) {
+        return ApiResponse::problem(errors.into());
+    }
+
+    match share_puzzle(store, puzzle, host.now) {
+        Ok(shared) => ApiResponse::created(&shared.url, &shared),
+        Err(e @ ShareError::IdConflict { .. }) => {
+            ApiResponse::problem(ProblemDetails::new(409, "Conflict", e.to_string()))
+        }
+        Err(e @ ShareError::Store(_)) => ApiResponse::problem(ProblemDetails::new(
+            503,
+            "Service Unavailable",
+            e.to_string(),
+        )),
+    }
+}
+
+/// Returns a shared puzzle, in printable formats like `POST /generate`
+fn shared_puzzle(request: &ApiRequest, host: &Host, id: &str) -> ApiResponse {
+    let Some(store) = host.puzzles else {
+        return no_puzzle_store();
+    };
+
+    match Some(id)
+        .filter(|id| is_puzzle_id(id))
+        .and_then(|id| load_shared_puzzle(store, id, host.now))
+    {
+        Some(puzzle) => negotiate_puzzle(request, &puzzle),
+        None => not_found(&request.path),
+    }
+}
+
+/// Printable formats are selected with the `Accept` header
+#[cfg(feature = "render")]
+fn negotiate_puzzle(request: &ApiRequest, puzzle: &Puzzle) -> ApiResponse {
+    use crate::render::{render_answer_key_svg, render_pdf, render_svg};
+
+    if request.accept.contains("application/pdf") {
+        let pdf = render_pdf(puzzle, &render_options(&request.query));
+        ApiResponse::new(200, Some("application/pdf"), pdf)
+    } else if request.accept.contains("image/svg+xml") {
+        let options = render_options(&request.query);
+        let svg = if options.answer_key {
+            render_answer_key_svg(puzzle, &options)
+        } else {
+            render_svg(puzzle, &options)
+        };
+        ApiResponse::new(200, Some("image/svg+xml"), svg.into_bytes())
+    } else {
+        ApiResponse::json(puzzle)
+    }
+}
+
+#[cfg(not(feature = "render"))]
+fn negotiate_puzzle(_request: &ApiRequest, puzzle: &Puzzle) -> ApiResponse {
+    ApiResponse::json(puzzle)
+}
+
+/// Generates many puzzles at once, one JSON object per line (NDJSON).
+/// Puzzles that fail contain an `error` instead of a `puzzle`. Lines are
+/// streamed as soon as their puzzle is generated.
+fn generate_puzzle_batch(request: &ApiRequest) -> ApiResponse {
+    let options: BatchOptions = match parse_body(request) {
+        Ok(options) => options,
+        Err(response) => return response,
+    };
+
+    if let Err(errors) = validate_batch(&options) {
+        return ApiResponse::problem(errors.into());
+    }
+
+    let lines = generate_batch_iter(options).map(|item| item.to_ndjson_line().into_bytes());
+    ApiResponse::streamed(NDJSON_CONTENT_TYPE, BodyStream::new(lines))
+}
+
+fn generate_crossword_puzzle(request: &ApiRequest) -> ApiResponse {
+    let options: CrosswordOptions = match parse_body(request) {
+        Ok(options) => options,
+        Err(response) => return response,
+    };
+
+    if let Err(errors) = validate_crossword(&options) {
+        return ApiResponse::problem(errors.into());
+    }
+
+    match generate_crossword(options) {
+        Ok(crossword) => ApiResponse::json(&crossword),
+        Err(e) => ApiResponse::problem(e.into()),
+    }
+}
+
+/// Reads render options from the query string (`?title=...&answerKey=true`)
+#[cfg(feature = "render")]
+fn render_options(query: &str) -> crate::render::RenderOptions {
+    let mut options = crate::render::RenderOptions::default();
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "title" => options.title = percent_decode(value),
+            "answerKey" => options.answer_key = value == "true",
+            _ => {}
+        }
+    }
+
+    options
+}
+
+/// Decodes `%XX` escapes and `+` in query string values
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (b'+', _) => decoded.push(b' '),
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The suite expects printable formats
+    #[cfg(feature = "render")]
+    #[test]
+    fn core_passes_conformance_suite() {
+        let store = MemoryPuzzleStore::default();
+        conformance::run(|request| handle(&request, &Host::new("Hello!", 0).with_puzzles(&store)));
+    }
+
+    #[test]
+    fn decodes_query_values() {
+        assert_eq!(percent_decode("My+Puzzle%21"), "My Puzzle!");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%C3%BC"), "ü");
+    }
+
+    #[test]
+    fn shared_puzzles() {
+        let generate = ApiRequest::new(Method::Post, "/generate")
+            .with_body(r#"{"size": 6, "words": ["RUST", "WASM"]}"#);
+        let puzzle = handle(&generate, &Host::new("", 0)).body;
+        let share = ApiRequest::new(Method::Post, "/puzzles").with_body(puzzle.clone());
+
+        // Hosts without storage cannot share puzzles
+        assert_eq!(handle(&share, &Host::new("", 0)).status, 503);
+
+        let store = MemoryPuzzleStore::default();
+        let host = Host::new("", 0).with_puzzles(&store);
+        let response = handle(&share, &host);
+        assert_eq!(response.status, 201);
+        let location = response.header("Location").unwrap().to_string();
+
+        let shared = ApiRequest::new(Method::Get, &location);
+        assert_eq!(handle(&shared, &host).body, puzzle);
+        let expired = Host::new("", SHARE_TTL_SECONDS * 1000).with_puzzles(&store);
+        assert_eq!(handle(&shared, &expired).status, 404);
+        assert_eq!(
+            handle(&ApiRequest::new(Method::Get, "/puzzles/unknown"), &host).status,
+            404
+        );
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn printable_formats() {
+        let request = ApiRequest::new(Method::Post, "/generate")
+            .with_body(r#"{"size": 6, "words": ["RUST", "WASM"]}"#)
+            .with_query("title=Test&answerKey=true");
+
+        let host = Host::new("", 0);
+        let pdf = handle(&request.clone().with_accept("application/pdf"), &host);
+        assert_eq!(pdf.header("content-type"), Some("application/pdf"));
+        assert!(pdf.body.starts_with(b"%PDF"));
+
+        let svg = handle(&request.with_accept("image/svg+xml"), &host);
+        assert_eq!(svg.header("Content-Type"), Some("image/svg+xml"));
+        assert!(String::from_utf8(svg.body)
+            .unwrap()
+            .contains("Test (Answer Key)"));
+    }
+}
@@ -0,0 +1,322 @@
+//! Builds the OpenAPI document served from `GET /openapi.json`.
+//!
+//! Schemas are derived from the wire types with `schemars`, which is only
+//! needed while testing. The document is checked in as `openapi.json` and
+//! served as a static file, so the hosts do not pay for schema generation.
+//! Run `UPDATE_OPENAPI=1 cargo test` after changing a wire type.
+
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    JsonSchema,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    BatchItem, BatchOptions, Crossword, CrosswordOptions, GeneratorOptions, ProblemDetails, Puzzle,
+    ThemeLibrary, NDJSON_CONTENT_TYPE,
+};
+
+use super::SharedPuzzle;
+
+use super::{JSON_CONTENT_TYPE, OPENAPI_JSON};
+
+const OPENAPI_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+fn schema_for<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(generator.subschema_for::<T>()).unwrap()
+}
+
+fn openapi_document() -> Value {
+    // OpenAPI 3.1 uses plain JSON Schema, so schemas can be validated with any validator
+    let settings = SchemaSettings::draft2019_09().with(|s| {
+        s.definitions_path = "#/components/schemas/".to_string();
+        s.option_add_null_type = true;
+        s.option_nullable = false;
+    });
+    let mut generator = settings.into_generator();
+
+    let problem = json!({
+        "description": "Invalid request or puzzle could not be generated",
+        "content": { ProblemDetails::CONTENT_TYPE: { "schema": schema_for::<ProblemDetails>(&mut generator) } }
+    });
+    let too_many_requests = json!({
+        "description": "Rate limit exceeded",
+        "headers": {
+            "Retry-After": { "description": "Seconds until the request can be retried", "schema": { "type": "integer" } }
+        },
+        "content": problem["content"]
+    });
+    let json_body = |schema: Value| json!({ "required": true, "content": { JSON_CONTENT_TYPE: { "schema": schema } } });
+    let binary = json!({ "type": "string", "format": "binary" });
+
+    let generate = json!({
+        "post": {
+            "summary": "Generates a word search puzzle",
+            "parameters": [
+                { "name": "title", "in": "query", "description": "Title of printable formats", "schema": { "type": "string" } },
+                { "name": "answerKey", "in": "query", "description": "Adds the answer key to printable formats", "schema": { "type": "boolean" } }
+            ],
+            "requestBody": json_body(schema_for::<GeneratorOptions>(&mut generator)),
+            "responses": {
+                "200": {
+                    "description": "Generated puzzle, printable formats are selected with the Accept header",
+                    "content": {
+                        JSON_CONTENT_TYPE: { "schema": schema_for::<Puzzle>(&mut generator) },
+                        "application/pdf": { "schema": binary },
+                        "image/svg+xml": { "schema": { "type": "string" } }
+                    }
+                },
+                "400": problem,
+                "429": too_many_requests
+            }
+        }
+    });
+
+    let batch = json!({
+        "post": {
+            "summary": "Generates many puzzles at once",
+            "requestBody": json_body(schema_for::<BatchOptions>(&mut generator)),
+            "responses": {
+                "200": {
+                    "description": "One JSON object per line and puzzle, failed puzzles contain an error",
+                    "content": { NDJSON_CONTENT_TYPE: { "itemSchema": schema_for::<BatchItem>(&mut generator) } }
+                },
+                "400": problem,
+                "413": { "description": "More puzzles than the rate limit allows at once", "content": problem["content"] },
+                "429": too_many_requests
+            }
+        }
+    });
+
+    let crossword = json!({
+        "post": {
+            "summary": "Generates a crossword",
+            "requestBody": json_body(schema_for::<CrosswordOptions>(&mut generator)),
+            "responses": {
+                "200": {
+                    "description": "Generated crossword",
+                    "content": { JSON_CONTENT_TYPE: { "schema": schema_for::<Crossword>(&mut generator) } }
+                },
+                "400": problem,
+                "429": too_many_requests
+            }
+        }
+    });
+
+    let share = json!({
+        "post": {
+            "summary": "Stores a generated puzzle and returns a share link",
+            "requestBody": json_body(schema_for::<Puzzle>(&mut generator)),
+            "responses": {
+                "201": {
+                    "description": "Shared puzzle, links expire after 30 days",
+                    "headers": {
+                        "Location": { "description": "Path of the shared puzzle", "schema": { "type": "string" } }
+                    },
+                    "content": { JSON_CONTENT_TYPE: { "schema": schema_for::<SharedPuzzle>(&mut generator) } }
+                },
+                "400": problem,
+                "409": { "description": "A different puzzle is already shared with the same ID", "content": problem["content"] },
+                "429": too_many_requests,
+                "503": { "description": "Sharing is not available on this host", "content": problem["content"] }
+            }
+        }
+    });
+
+    let shared = json!({
+        "get": {
+            "summary": "Returns a shared puzzle",
+            "parameters": [
+                { "name": "id", "in": "path", "required": true, "schema": { "type": "string", "pattern": "^[0-9A-Za-z]{11}$" } },
+                { "name": "title", "in": "query", "description": "Title of printable formats", "schema": { "type": "string" } },
+                { "name": "answerKey", "in": "query", "description": "Adds the answer key to printable formats", "schema": { "type": "boolean" } }
+            ],
+            "responses": {
+                "200": generate["post"]["responses"]["200"],
+                "404": { "description": "Unknown or expired puzzle", "content": problem["content"] },
+                "503": { "description": "Sharing is not available on this host", "content": problem["content"] }
+            }
+        }
+    });
+
+    let themes = json!({
+        "get": {
+            "summary": "Lists the bundled word list themes",
+            "responses": {
+                "200": {
+                    "description": "Available themes",
+                    "content": { JSON_CONTENT_TYPE: { "schema": schema_for::<ThemeLibrary>(&mut generator) } }
+                }
+            }
+        }
+    });
+
+    let openapi = json!({
+        "get": {
+            "summary": "This document",
+            "responses": {
+                "200": {
+                    "description": "OpenAPI document",
+                    "content": { JSON_CONTENT_TYPE: { "schema": { "type": "object" } } }
+                }
+            }
+        }
+    });
+
+    // schemars forbids additional properties in the variants of externally tagged
+    // enums, which breaks flattening them next to other fields
+    let mut schemas = serde_json::to_value(generator.take_definitions()).unwrap();
+    for variant in schemas["BatchItem"]["oneOf"].as_array_mut().unwrap() {
+        variant
+            .as_object_mut()
+            .unwrap()
+            .remove("additionalProperties");
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Word Puzzle API",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": {
+            "/generate": generate,
+            "/generate/batch": batch,
+            "/generate/crossword": crossword,
+            "/puzzles": share,
+            "/puzzles/{id}": shared,
+            "/themes": themes,
+            "/openapi.json": openapi
+        },
+        "components": {
+            "schemas": schemas
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{handle, ApiRequest, Host, MemoryPuzzleStore, Method};
+
+    #[test]
+    fn openapi_document_is_up_to_date() {
+        let generated = serde_json::to_string_pretty(&openapi_document()).unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(OPENAPI_PATH, &generated).unwrap();
+            return;
+        }
+
+        assert!(
+            OPENAPI_JSON == generated,
+            "openapi.json is outdated, run `UPDATE_OPENAPI=1 cargo test`"
+        );
+    }
+
+    /// Validates `instance` against a schema of the document's components
+    fn assert_matches_schema(component: &str, instance: &Value) {
+        let document: Value = serde_json::from_str(OPENAPI_JSON).unwrap();
+        let schema = json!({
+            "$ref": format!("#/components/schemas/{component}"),
+            "components": document["components"]
+        });
+        let validator = jsonschema::draft201909::new(&schema).unwrap();
+        let errors: Vec<String> = validator
+            .iter_errors(instance)
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect();
+        assert!(errors.is_empty(), "{component}: {errors:?}\n{instance:#}");
+    }
+
+    fn post(path: &str, body: &Value) -> Value {
+        let request =
+            ApiRequest::new(Method::Post, path).with_body(serde_json::to_vec(body).unwrap());
+        serde_json::from_slice(&handle(&request, &Host::new("", 0)).body).unwrap()
+    }
+
+    #[test]
+    fn sample_requests_match_schema() {
+        let requests = [
+            json!({ "size": 10, "words": ["RUST", "WASM"] }),
+            json!({ "size": 12, "theme": "animals", "language": "de", "word_count": 5 }),
+            json!({
+                "width": 8,
+                "height": 6,
+                "mask": { "shape": "heart" },
+                "hidden_message": "HI",
+                "target_difficulty": "hard",
+                "blocklist": ["BAD"],
+                "words": ["CLOUD"]
+            }),
+            json!({ "mask": { "ascii": ["XX.", ".XX"] }, "words": ["AB"] }),
+        ];
+        for request in requests.iter() {
+            assert_matches_schema("GeneratorOptions", request);
+        }
+
+        assert_matches_schema(
+            "CrosswordOptions",
+            &json!({ "max_size": 10, "entries": [{ "word": "RUST", "clue": "Language" }] }),
+        );
+        assert_matches_schema("BatchOptions", &json!({ "puzzles": requests }));
+    }
+
+    #[test]
+    fn invalid_request_does_not_match_schema() {
+        let document: Value = serde_json::from_str(OPENAPI_JSON).unwrap();
+        let schema = json!({
+            "$ref": "#/components/schemas/GeneratorOptions",
+            "components": document["components"]
+        });
+        let validator = jsonschema::draft201909::new(&schema).unwrap();
+        assert!(!validator.is_valid(&json!({ "size": "large", "words": ["RUST"] })));
+        assert!(!validator.is_valid(&json!({ "size": 10 , "target_difficulty": "extreme" })));
+    }
+
+    #[test]
+    fn responses_match_schema() {
+        let puzzle = post(
+            "/generate",
+            &json!({ "size": 8, "words": ["RUST", "WASM"], "target_difficulty": "hard" }),
+        );
+        assert_matches_schema("Puzzle", &puzzle);
+
+        let crossword = post(
+            "/generate/crossword",
+            &json!({ "entries": [{ "word": "RUST", "clue": "Language" }, { "word": "TRUST", "clue": "Belief" }] }),
+        );
+        assert_matches_schema("Crossword", &crossword);
+
+        for problem in [
+            post("/generate", &json!({ "size": 21, "words": [] })),
+            post(
+                "/generate",
+                &json!({ "size": 10, "theme": "unknown", "blocklist": ["A"], "words": ["A", "a", "1"] }),
+            ),
+            post(
+                "/generate",
+                &json!({ "size": 3, "words": ["AB"], "hidden_message": "TOOLONGMESSAGE" }),
+            ),
+        ] {
+            assert_matches_schema("ProblemDetails", &problem);
+        }
+
+        let request = ApiRequest::new(Method::Post, "/generate/batch").with_body(
+            r#"{"puzzles": [{"size": 8, "words": ["RUST"]}, {"size": 8, "words": []}]}"#,
+        );
+        let response = handle(&request, &Host::new("", 0)).collect_stream();
+        let body = String::from_utf8(response.body).unwrap();
+        for line in body.lines() {
+            assert_matches_schema("BatchItem", &serde_json::from_str(line).unwrap());
+        }
+
+        let themes = handle(&ApiRequest::new(Method::Get, "/themes"), &Host::new("", 0)).body;
+        assert_matches_schema("ThemeLibrary", &serde_json::from_slice(&themes).unwrap());
+
+        let store = MemoryPuzzleStore::default();
+        let request = ApiRequest::new(Method::Post, "/puzzles")
+            .with_body(serde_json::to_vec(&puzzle).unwrap());
+        let shared = handle(&request, &Host::new("", 0).with_puzzles(&store)).body;
+        assert_matches_schema("SharedPuzzle", &serde_json::from_slice(&shared).unwrap());
+    }
+}
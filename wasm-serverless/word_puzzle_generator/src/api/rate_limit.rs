@@ -0,0 +1,435 @@
+//! Token bucket rate limiting for the CPU-heavy generator routes.
+//!
+//! Every client has a bucket that holds up to [`RateLimitPolicy::capacity`]
+//! tokens and refills continuously. Requests take tokens depending on their
+//! cost (see [`ApiRequest::cost`]); if there are not enough tokens, the request
+//! is answered with `429 Too Many Requests` and a `Retry-After` header.
+//! Requests costing more than the capacity can never succeed, they are
+//! answered with `413 Content Too Large`. The default capacity is the largest
+//! batch, so only hosts with a smaller capacity reject valid batches.
+//!
+//! Clients are identified by their address, or by an API key if the host
+//! configured it (see [`ApiKeys`]). Buckets are kept in a [`RateLimitStore`],
+//! so hosts can use their own storage (Spin key-value store, Cloudflare KV,
+//! memory).
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+
+use super::{handle, ApiRequest, ApiResponse, Host, Method};
+use crate::{ProblemDetails, MAX_BATCH_SIZE};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitPolicy {
+    /// Maximum number of tokens (burst size)
+    pub capacity: f64,
+    /// Tokens added per second
+    pub refill_per_second: f64,
+}
+
+impl Default for RateLimitPolicy {
+    /// 30 puzzles per minute with bursts of up to one batch of the largest size
+    fn default() -> Self {
+        Self {
+            capacity: MAX_BATCH_SIZE as f64,
+            refill_per_second: 0.5f64,
+        }
+    }
+}
+
+/// State of a client's bucket as kept in a [`RateLimitStore`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBucket {
+    pub tokens: f64,
+    /// Time of the last update in milliseconds since the Unix epoch
+    pub updated_at: u64,
+}
+
+/// Storage for token buckets.
+///
+/// Stores should fail open: if the storage is not available, `load` returns
+/// `None` and `save` does nothing, so that clients are not locked out.
+///
+/// A missing bucket is the same as a full one, so stores should drop buckets
+/// `ttl_seconds` after they were saved (see [`RateLimiter::ttl_seconds`]).
+pub trait RateLimitStore {
+    fn load(&self, key: &str) -> Option<TokenBucket>;
+    fn save(&self, key: &str, bucket: &TokenBucket, ttl_seconds: u64);
+}
+
+/// Store keeping buckets in memory, for native hosts and tests.
+/// Expired buckets are dropped whenever a bucket is saved.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    /// Buckets and their expiry in milliseconds since the Unix epoch
+    buckets: Mutex<HashMap<String, (TokenBucket, u64)>>,
+}
+
+impl RateLimitStore for MemoryStore {
+    fn load(&self, key: &str) -> Option<TokenBucket> {
+        self.buckets
+            .lock()
+            .ok()?
+            .get(key)
+            .map(|(bucket, _)| *bucket)
+    }
+
+    fn save(&self, key: &str, bucket: &TokenBucket, ttl_seconds: u64) {
+        if let Ok(mut buckets) = self.buckets.lock() {
+            let now = bucket.updated_at;
+            buckets.retain(|_, (_, expires_at)| *expires_at > now);
+            buckets.insert(key.to_string(), (*bucket, now + ttl_seconds * 1000));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitDecision {
+    Allowed,
+    /// Seconds until enough tokens are available again
+    Limited {
+        retry_after: u64,
+    },
+    /// The request costs more than a full bucket holds, it is never allowed
+    TooExpensive {
+        cost: f64,
+        capacity: f64,
+    },
+}
+
+impl RateLimitDecision {
+    /// Response rejecting the request, `None` if it is allowed
+    pub fn rejection(self) -> Option<ApiResponse> {
+        match self {
+            RateLimitDecision::Allowed => None,
+            RateLimitDecision::Limited { retry_after } => Some(too_many_requests(retry_after)),
+            RateLimitDecision::TooExpensive { cost, capacity } => {
+                Some(too_expensive(cost, capacity))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RateLimiter {
+    pub policy: RateLimitPolicy,
+}
+
+impl RateLimiter {
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self { policy }
+    }
+
+    /// Takes `cost` tokens from the client's bucket if possible.
+    /// `now` is in milliseconds since the Unix epoch.
+    pub fn check(
+        &self,
+        store: &impl RateLimitStore,
+        key: &str,
+        cost: f64,
+        now: u64,
+    ) -> RateLimitDecision {
+        let RateLimitPolicy {
+            capacity,
+            refill_per_second,
+        } = self.policy;
+        if cost > capacity {
+            return RateLimitDecision::TooExpensive { cost, capacity };
+        }
+
+        // New clients start with a full bucket
+        let bucket = store.load(key).unwrap_or(TokenBucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        let elapsed = now.saturating_sub(bucket.updated_at) as f64 / 1000f64;
+        let tokens = (bucket.tokens + elapsed * refill_per_second).min(capacity);
+
+        if tokens >= cost {
+            store.save(
+                key,
+                &TokenBucket {
+                    tokens: tokens - cost,
+                    updated_at: now,
+                },
+                self.ttl_seconds(),
+            );
+            RateLimitDecision::Allowed
+        } else {
+            let retry_after = ((cost - tokens) / refill_per_second).ceil();
+            RateLimitDecision::Limited {
+                retry_after: (retry_after as u64).max(1),
+            }
+        }
+    }
+
+    /// Seconds until an empty bucket is full again. Stores can drop buckets
+    /// that have not been updated for this long.
+    pub fn ttl_seconds(&self) -> u64 {
+        (self.policy.capacity / self.policy.refill_per_second).ceil() as u64
+    }
+}
+
+impl ApiRequest {
+    /// Number of tokens the request takes, `0` for requests that are not limited.
+    /// Batches take one token per puzzle.
+    pub fn cost(&self) -> f64 {
+        match (self.method, self.path.trim_end_matches('/')) {
+            (Method::Post, "/generate" | "/generate/crossword" | "/puzzles") => 1f64,
+            (Method::Post, "/generate/batch") => batch_size(&self.body).max(1) as f64,
+            _ => 0f64,
+        }
+    }
+}
+
+/// Number of puzzles in a batch request, `0` if the body is malformed (the
+/// request is rejected anyway)
+fn batch_size(body: &[u8]) -> usize {
+    #[derive(Deserialize)]
+    struct Batch {
+        puzzles: Vec<IgnoredAny>,
+    }
+
+    serde_json::from_slice::<Batch>(body).map_or(0, |batch| batch.puzzles.len())
+}
+
+/// API keys known to the host, e.g. of customers with their own quota.
+///
+/// Unknown keys are ignored, otherwise clients could get a new bucket for
+/// every request by sending a new key.
+#[derive(Debug, Default, Clone)]
+pub struct ApiKeys(HashSet<String>);
+
+impl ApiKeys {
+    /// Parses a comma-separated list of keys (e.g. from an environment variable)
+    pub fn parse(keys: &str) -> Self {
+        Self(
+            keys.split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.contains(key)
+    }
+}
+
+/// Identifies the client, a known API key takes precedence over the IP address
+pub fn client_key(api_key: Option<&str>, address: Option<&str>, api_keys: &ApiKeys) -> String {
+    match (api_key.filter(|k| api_keys.contains(k)), address) {
+        (Some(key), _) => format!("key:{key}"),
+        (None, Some(address)) => format!("ip:{address}"),
+        (None, None) => "anonymous".to_string(),
+    }
+}
+
+/// `429 Too Many Requests` with `Retry-After` in seconds
+pub fn too_many_requests(retry_after: u64) -> ApiResponse {
+    let mut response = ApiResponse::problem(ProblemDetails::new(
+        429,
+        "Too Many Requests",
+        format!("rate limit exceeded, retry in {retry_after} seconds"),
+    ));
+    response
+        .headers
+        .push(("Retry-After".to_string(), retry_after.to_string()));
+    response
+}
+
+/// `413 Content Too Large` for requests costing more than a full bucket
+pub fn too_expensive(cost: f64, capacity: f64) -> ApiResponse {
+    ApiResponse::problem(ProblemDetails::new(
+        413,
+        "Content Too Large",
+        format!("the request counts as {cost} puzzles, at most {capacity} are allowed at once"),
+    ))
+}
+
+/// Like [`handle`], but rejects requests of clients that exceeded their rate limit
+pub fn handle_rate_limited(
+    request: &ApiRequest,
+    host: &Host,
+    limiter: &RateLimiter,
+    store: &impl RateLimitStore,
+    client: &str,
+) -> ApiResponse {
+    let cost = request.cost();
+    if cost > 0f64 {
+        if let Some(rejection) = limiter.check(store, client, cost, host.now).rejection() {
+            return rejection;
+        }
+    }
+
+    handle(request, host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitPolicy {
+            capacity: 2f64,
+            refill_per_second: 0.5f64,
+        })
+    }
+
+    #[test]
+    fn bucket_empties_and_refills() {
+        let (limiter, store) = (limiter(), MemoryStore::default());
+        assert_eq!(
+            limiter.check(&store, "a", 1f64, 0),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            limiter.check(&store, "a", 1f64, 0),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            limiter.check(&store, "a", 1f64, 500),
+            RateLimitDecision::Limited { retry_after: 2 }
+        );
+
+        // Other clients have their own bucket
+        assert_eq!(
+            limiter.check(&store, "b", 1f64, 500),
+            RateLimitDecision::Allowed
+        );
+
+        // One token after two seconds, never more than the capacity
+        assert_eq!(
+            limiter.check(&store, "a", 1f64, 2000),
+            RateLimitDecision::Allowed
+        );
+        limiter.check(&store, "a", 0f64, 1_000_000);
+        assert_eq!(store.load("a").unwrap().tokens, 2f64);
+    }
+
+    #[test]
+    fn requests_costing_more_than_the_capacity_are_rejected() {
+        let (limiter, store) = (limiter(), MemoryStore::default());
+        assert_eq!(
+            limiter.check(&store, "a", 3f64, 0),
+            RateLimitDecision::TooExpensive {
+                cost: 3f64,
+                capacity: 2f64
+            }
+        );
+        assert_eq!(
+            limiter.check(&store, "a", 2f64, 0),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            limiter.check(&store, "a", 2f64, 0),
+            RateLimitDecision::Limited { retry_after: 4 }
+        );
+    }
+
+    #[test]
+    fn batches_cost_one_token_per_puzzle() {
+        let batch = |body: &str| ApiRequest::new(Method::Post, "/generate/batch").with_body(body);
+        assert_eq!(batch(r#"{"puzzles": [{}, {}, "x"]}"#).cost(), 3f64);
+        assert_eq!(batch(r#"{"puzzles": []}"#).cost(), 1f64);
+        assert_eq!(batch("not json").cost(), 1f64);
+
+        let (limiter, store) = (limiter(), MemoryStore::default());
+        let response = handle_rate_limited(
+            &batch(r#"{"puzzles": [{}, {}, {}]}"#),
+            &Host::new("", 0),
+            &limiter,
+            &store,
+            "a",
+        );
+        assert_eq!(response.status, 413);
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(ProblemDetails::CONTENT_TYPE)
+        );
+    }
+
+    #[test]
+    fn default_policy_accepts_largest_batch() {
+        let puzzles = vec!["{}"; MAX_BATCH_SIZE].join(", ");
+        let batch = ApiRequest::new(Method::Post, "/generate/batch")
+            .with_body(format!(r#"{{"puzzles": [{puzzles}]}}"#));
+        let limiter = RateLimiter::new(RateLimitPolicy::default());
+        let store = MemoryStore::default();
+        assert_eq!(
+            limiter.check(&store, "a", batch.cost(), 0),
+            RateLimitDecision::Allowed
+        );
+        assert_eq!(
+            limiter.check(&store, "a", 1f64, 0),
+            RateLimitDecision::Limited { retry_after: 2 }
+        );
+    }
+
+    #[test]
+    fn memory_store_drops_expired_buckets() {
+        let (limiter, store) = (limiter(), MemoryStore::default());
+        limiter.check(&store, "a", 1f64, 0);
+        limiter.check(&store, "b", 1f64, 3000);
+        assert!(store.load("a").is_some());
+
+        // Full again after four seconds
+        limiter.check(&store, "b", 1f64, 4000);
+        assert!(store.load("a").is_none());
+        assert!(store.load("b").is_some());
+    }
+
+    #[test]
+    fn rejected_requests_get_429() {
+        let (limiter, store) = (limiter(), MemoryStore::default());
+        let generate = ApiRequest::new(Method::Post, "/generate")
+            .with_body(r#"{"size": 6, "words": ["RUST"]}"#);
+        let themes = ApiRequest::new(Method::Get, "/themes");
+        let host = Host::new("", 0);
+
+        for _ in 0..2 {
+            assert_eq!(
+                handle_rate_limited(&generate, &host, &limiter, &store, "a").status,
+                200
+            );
+        }
+        let response = handle_rate_limited(&generate, &host, &limiter, &store, "a");
+        assert_eq!(response.status, 429);
+        assert_eq!(response.header("Retry-After"), Some("2"));
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(ProblemDetails::CONTENT_TYPE)
+        );
+
+        // Cheap routes are never limited
+        assert_eq!(
+            handle_rate_limited(&themes, &host, &limiter, &store, "a").status,
+            200
+        );
+    }
+
+    #[test]
+    fn client_keys() {
+        let api_keys = ApiKeys::parse("secret, other,");
+        assert_eq!(
+            client_key(Some("secret"), Some("1.2.3.4"), &api_keys),
+            "key:secret"
+        );
+        assert_eq!(client_key(Some("other"), None, &api_keys), "key:other");
+        assert_eq!(
+            client_key(Some("guessed"), Some("1.2.3.4"), &api_keys),
+            "ip:1.2.3.4"
+        );
+        assert_eq!(
+            client_key(Some(""), Some("1.2.3.4"), &ApiKeys::parse("")),
+            "ip:1.2.3.4"
+        );
+        assert_eq!(client_key(None, None, &api_keys), "anonymous");
+    }
+}
@@ -0,0 +1,377 @@
+//! Share links for puzzles.
+//!
+//! `POST /puzzles` stores a puzzle and returns its ID, `GET /puzzles/{id}`
+//! returns it again. IDs are derived from the puzzle's content, so sharing the
+//! same puzzle twice yields the same link. Shared puzzles expire after
+//! [`SHARE_TTL_SECONDS`]; sharing a puzzle again extends its lifetime.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{Puzzle, ValidationError, WordPlacement, MAX_SIZE, MIN_SIZE};
+
+/// Lifetime of shared puzzles (30 days)
+pub const SHARE_TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// Most words (and placements) of a shared puzzle, one per cell of the largest grid
+pub const MAX_SHARED_WORDS: usize = MAX_SIZE * MAX_SIZE;
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Puzzle as kept in a [`PuzzleStore`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredPuzzle {
+    pub puzzle: Puzzle,
+    /// Milliseconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+/// Response of `POST /puzzles`
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SharedPuzzle {
+    pub id: String,
+    /// Path of the shared puzzle, relative to the host
+    pub url: String,
+    /// Milliseconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("puzzle store failed: {0}")]
+pub struct StoreError(pub String);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShareError {
+    /// IDs are short, so different puzzles might get the same one. The
+    /// puzzle shared first keeps it until it expires.
+    #[error("a different puzzle is already shared as {id}")]
+    IdConflict { id: String },
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Storage for shared puzzles.
+///
+/// Stores do not need to remove expired puzzles, [`load_shared_puzzle`]
+/// ignores them. Stores with built-in expiry can use [`SHARE_TTL_SECONDS`].
+pub trait PuzzleStore {
+    fn load(&self, id: &str) -> Option<StoredPuzzle>;
+    fn save(&self, id: &str, puzzle: &StoredPuzzle) -> Result<(), StoreError>;
+}
+
+/// Store keeping puzzles in memory, for native hosts and tests
+#[derive(Debug, Default)]
+pub struct MemoryPuzzleStore {
+    puzzles: Mutex<HashMap<String, StoredPuzzle>>,
+}
+
+impl MemoryPuzzleStore {
+    /// All stored puzzles, e.g. to write them to an async store afterwards
+    pub fn entries(&self) -> Vec<(String, StoredPuzzle)> {
+        self.puzzles
+            .lock()
+            .map(|p| p.iter().map(|(id, p)| (id.clone(), p.clone())).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl PuzzleStore for MemoryPuzzleStore {
+    fn load(&self, id: &str) -> Option<StoredPuzzle> {
+        self.puzzles.lock().ok()?.get(id).cloned()
+    }
+
+    fn save(&self, id: &str, puzzle: &StoredPuzzle) -> Result<(), StoreError> {
+        self.puzzles
+            .lock()
+            .map_err(|e| StoreError(e.to_string()))?
+            .insert(id.to_string(), puzzle.clone());
+        Ok(())
+    }
+}
+
+/// Short ID derived from the content of the puzzle (SHA-256 hash of its JSON
+/// representation, first 64 bits in base 62, 11 characters)
+pub fn puzzle_id(puzzle: &Puzzle) -> String {
+    let json = serde_json::to_vec(puzzle).expect("puzzles are serializable");
+    let digest = Sha256::digest(json);
+    let mut hash = u64::from_be_bytes(digest[..8].try_into().expect("digests have 32 bytes"));
+
+    let mut id = Vec::new();
+    for _ in 0..11 {
+        id.push(BASE62[(hash % 62) as usize]);
+        hash /= 62;
+    }
+    String::from_utf8(id).expect("base 62 digits are ASCII")
+}
+
+/// Checks whether a string can be an ID returned by [`puzzle_id`]
+pub fn is_puzzle_id(id: &str) -> bool {
+    id.len() == 11 && id.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Checks a puzzle sent by a client: the grid must be rectangular and within
+/// the supported size, every word must have exactly one placement, and every
+/// placement must match the letters in the grid.
+pub fn validate_puzzle(puzzle: &Puzzle) -> Result<(), Vec<ValidationError>> {
+    // Check the counts first, the other checks take longer with more words
+    let count = puzzle.words.len().max(puzzle.placements.len());
+    if count > MAX_SHARED_WORDS {
+        return Err(vec![ValidationError::TooManyWords {
+            count,
+            max: MAX_SHARED_WORDS,
+        }]);
+    }
+
+    let rows: Vec<Vec<char>> = puzzle.grid.iter().map(|r| r.chars().collect()).collect();
+    let (width, height) = (rows.first().map_or(0, Vec::len), rows.len());
+
+    let mut errors = Vec::new();
+    for (dimension, size) in [("width", width), ("height", height)] {
+        if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+            errors.push(ValidationError::SizeOutOfRange {
+                dimension,
+                size,
+                min: MIN_SIZE,
+                max: MAX_SIZE,
+            });
+        }
+    }
+
+    for (row, cells) in rows.iter().enumerate() {
+        if cells.len() != width {
+            errors.push(ValidationError::RaggedGrid {
+                row,
+                length: cells.len(),
+                width,
+            });
+        }
+    }
+
+    let mut unplaced: Vec<&String> = puzzle.words.iter().collect();
+    for placement in puzzle.placements.iter() {
+        match unplaced.iter().position(|word| **word == placement.word) {
+            Some(index) => {
+                unplaced.swap_remove(index);
+            }
+            None => errors.push(ValidationError::UnlistedPlacement {
+                word: placement.word.clone(),
+            }),
+        }
+        if !placement_matches(&rows, placement) {
+            errors.push(ValidationError::PlacementMismatch {
+                word: placement.word.clone(),
+            });
+        }
+    }
+    errors.extend(
+        unplaced
+            .into_iter()
+            .map(|word| ValidationError::MissingPlacement { word: word.clone() }),
+    );
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Like comparing [`WordPlacement::cells`] with the grid, but without
+/// trusting the placement to stay inside the grid
+fn placement_matches(rows: &[Vec<char>], placement: &WordPlacement) -> bool {
+    let (row_step, col_step) = placement.direction.delta();
+    placement.word.chars().enumerate().all(|(i, letter)| {
+        let (row, col) = if placement.reversed {
+            (
+                placement.row.checked_sub(i * row_step),
+                placement.col.checked_sub(i * col_step),
+            )
+        } else {
+            (
+                placement.row.checked_add(i * row_step),
+                placement.col.checked_add(i * col_step),
+            )
+        };
+        row.zip(col)
+            .and_then(|(row, col)| rows.get(row)?.get(col))
+            .is_some_and(|cell| cell.to_uppercase().eq(letter.to_uppercase()))
+    })
+}
+
+/// Stores the puzzle; `now` is in milliseconds since the Unix epoch.
+/// Fails if a different puzzle with the same ID has not expired yet.
+pub fn share_puzzle(
+    store: &dyn PuzzleStore,
+    puzzle: Puzzle,
+    now: u64,
+) -> Result<SharedPuzzle, ShareError> {
+    let id = puzzle_id(&puzzle);
+    if load_shared_puzzle(store, &id, now).is_some_and(|shared| shared != puzzle) {
+        return Err(ShareError::IdConflict { id });
+    }
+
+    let expires_at = now + SHARE_TTL_SECONDS * 1000;
+    store.save(&id, &StoredPuzzle { puzzle, expires_at })?;
+
+    Ok(SharedPuzzle {
+        url: format!("/puzzles/{id}"),
+        id,
+        expires_at,
+    })
+}
+
+/// Loads a shared puzzle unless it is unknown or expired
+pub fn load_shared_puzzle(store: &dyn PuzzleStore, id: &str, now: u64) -> Option<Puzzle> {
+    store
+        .load(id)
+        .filter(|stored| stored.expires_at > now)
+        .map(|stored| stored.puzzle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{place_words, Direction, GeneratorOptions};
+
+    fn puzzle(word: &str) -> Puzzle {
+        Puzzle {
+            grid: vec![word.to_string()],
+            words: vec![word.to_string()],
+            placements: vec![WordPlacement {
+                word: word.to_string(),
+                row: 0,
+                col: 0,
+                direction: Direction::Horizontal,
+                reversed: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn ids_are_content_addressed() {
+        let id = puzzle_id(&puzzle("RUST"));
+        assert!(is_puzzle_id(&id));
+        assert_eq!(id, puzzle_id(&puzzle("RUST")));
+        assert_ne!(id, puzzle_id(&puzzle("WASM")));
+
+        assert!(!is_puzzle_id("../secrets"));
+        assert!(!is_puzzle_id("short"));
+    }
+
+    #[test]
+    fn generated_puzzles_are_valid() {
+        let puzzle = place_words(GeneratorOptions {
+            size: 8,
+            words: vec!["RUST".to_string(), "WASM".to_string(), "SPIN".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(validate_puzzle(&puzzle), Ok(()));
+    }
+
+    #[test]
+    fn rejects_inconsistent_puzzles() {
+        let mut ragged = puzzle("RUST");
+        ragged.grid.push("AB".to_string());
+        assert_eq!(
+            validate_puzzle(&ragged),
+            Err(vec![ValidationError::RaggedGrid {
+                row: 1,
+                length: 2,
+                width: 4
+            }])
+        );
+
+        let mut unlisted = puzzle("RUST");
+        unlisted.grid.push("ABCD".to_string());
+        unlisted.words = vec!["RUSTY".to_string()];
+        assert_eq!(
+            validate_puzzle(&unlisted),
+            Err(vec![
+                ValidationError::UnlistedPlacement {
+                    word: "RUST".to_string()
+                },
+                ValidationError::MissingPlacement {
+                    word: "RUSTY".to_string()
+                }
+            ])
+        );
+
+        let mut duplicate = puzzle("RUST");
+        duplicate.grid.push("ABCD".to_string());
+        duplicate.placements.push(duplicate.placements[0].clone());
+        assert_eq!(
+            validate_puzzle(&duplicate),
+            Err(vec![ValidationError::UnlistedPlacement {
+                word: "RUST".to_string()
+            }])
+        );
+
+        let mut huge = puzzle("RUST");
+        huge.words = vec!["RUST".to_string(); MAX_SHARED_WORDS + 1];
+        assert_eq!(
+            validate_puzzle(&huge),
+            Err(vec![ValidationError::TooManyWords {
+                count: MAX_SHARED_WORDS + 1,
+                max: MAX_SHARED_WORDS
+            }])
+        );
+
+        // Reversed placement starting in the first column would leave the grid
+        let mut outside = puzzle("RUST");
+        outside.grid.push("ABCD".to_string());
+        outside.placements[0].reversed = true;
+        assert_eq!(
+            validate_puzzle(&outside),
+            Err(vec![ValidationError::PlacementMismatch {
+                word: "RUST".to_string()
+            }])
+        );
+    }
+
+    #[test]
+    fn shared_puzzles_expire() {
+        let store = MemoryPuzzleStore::default();
+        let shared = share_puzzle(&store, puzzle("RUST"), 1000).unwrap();
+        assert_eq!(shared.url, format!("/puzzles/{}", shared.id));
+        assert_eq!(shared.expires_at, 1000 + SHARE_TTL_SECONDS * 1000);
+
+        let loaded = load_shared_puzzle(&store, &shared.id, shared.expires_at - 1).unwrap();
+        assert_eq!(loaded.grid, vec!["RUST".to_string()]);
+        assert!(load_shared_puzzle(&store, &shared.id, shared.expires_at).is_none());
+        assert!(load_shared_puzzle(&store, "00000000000", 1000).is_none());
+    }
+
+    #[test]
+    fn ids_of_other_puzzles_are_not_overwritten() {
+        let store = MemoryPuzzleStore::default();
+        let shared = share_puzzle(&store, puzzle("RUST"), 1000).unwrap();
+
+        // Simulate a collision by storing another puzzle under the same ID
+        let other = StoredPuzzle {
+            puzzle: puzzle("WASM"),
+            expires_at: shared.expires_at,
+        };
+        store.save(&shared.id, &other).unwrap();
+        assert_eq!(
+            share_puzzle(&store, puzzle("RUST"), 2000).unwrap_err(),
+            ShareError::IdConflict {
+                id: shared.id.clone()
+            }
+        );
+        assert_eq!(
+            load_shared_puzzle(&store, &shared.id, 2000).unwrap().grid,
+            vec!["WASM".to_string()]
+        );
+
+        // Expired puzzles are replaced
+        let replaced = share_puzzle(&store, puzzle("RUST"), shared.expires_at).unwrap();
+        assert_eq!(replaced.id, shared.id);
+    }
+}
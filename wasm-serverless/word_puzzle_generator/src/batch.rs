@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{place_words, validate, GeneratorOptions, ProblemDetails, Puzzle, ValidationError};
+
+/// Maximum number of puzzles in a single batch
+pub const MAX_BATCH_SIZE: usize = 500;
+
+/// Content type of the batch response (one JSON object per line)
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+#[derive(Deserialize, Default)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct BatchOptions {
+    /// Options of every puzzle. Items are parsed one by one during generation,
+    /// so that malformed items do not fail the whole batch.
+    #[cfg_attr(test, schemars(with = "Vec<GeneratorOptions>"))]
+    pub puzzles: Vec<serde_json::Value>,
+}
+
+/// Outcome of a single puzzle of a batch
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum BatchOutcome {
+    Puzzle(Puzzle),
+    Error(ProblemDetails),
+}
+
+/// Result line of a batch, `index` refers to [`BatchOptions::puzzles`]
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct BatchItem {
+    pub index: usize,
+    #[serde(flatten)]
+    pub outcome: BatchOutcome,
+}
+
+impl BatchItem {
+    /// Serializes the item as a single NDJSON line (including the line break)
+    pub fn to_ndjson_line(&self) -> String {
+        let mut line =
+            serde_json::to_string(self).expect("batch items only contain serializable data");
+        line.push('\n');
+        line
+    }
+}
+
+/// Checks the size of the batch. The puzzles themselves are validated one
+/// by one during generation, so that invalid puzzles do not fail the whole batch.
+pub fn validate_batch(options: &BatchOptions) -> Result<(), Vec<ValidationError>> {
+    let count = options.puzzles.len();
+    if (1..=MAX_BATCH_SIZE).contains(&count) {
+        Ok(())
+    } else {
+        Err(vec![ValidationError::BatchSizeOutOfRange {
+            count,
+            min: 1,
+            max: MAX_BATCH_SIZE,
+        }])
+    }
+}
+
+/// Generates all puzzles of the batch, failures are reported per item.
+pub fn generate_batch(options: BatchOptions) -> Vec<BatchItem> {
+    generate_batch_iter(options).collect()
+}
+
+/// Generates the puzzles of the batch lazily, so that hosts can write each
+/// result as soon as it is available. Results are in the order of the puzzles.
+///
+/// Puzzles are generated in parallel in the background on native targets,
+/// generation stops when the iterator is dropped. On wasm there are no
+/// threads, so they are generated one after another while iterating.
+pub fn generate_batch_iter(options: BatchOptions) -> impl Iterator<Item = BatchItem> + Send {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::{collections::BTreeMap, sync::mpsc};
+
+        use rayon::prelude::*;
+
+        let count = options.puzzles.len();
+        let (sender, receiver) = mpsc::channel();
+        rayon::spawn(move || {
+            let _ = options
+                .puzzles
+                .into_par_iter()
+                .enumerate()
+                .try_for_each_with(sender, |sender, (index, options)| {
+                    sender.send(generate_item(index, options))
+                });
+        });
+
+        // Puzzles are finished in any order, keep the early ones until it is their turn
+        let mut finished = receiver.into_iter();
+        let mut pending = BTreeMap::new();
+        (0..count).map_while(move |index| loop {
+            if let Some(item) = pending.remove(&index) {
+                return Some(item);
+            }
+            let item: BatchItem = finished.next()?;
+            pending.insert(item.index, item);
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        options
+            .puzzles
+            .into_iter()
+            .enumerate()
+            .map(|(index, options)| generate_item(index, options))
+    }
+}
+
+fn generate_item(index: usize, options: serde_json::Value) -> BatchItem {
+    let outcome = match serde_json::from_value::<GeneratorOptions>(options) {
+        Err(e) => BatchOutcome::Error(ProblemDetails::bad_request(e.to_string())),
+        Ok(options) => match validate(&options) {
+            Err(errors) => BatchOutcome::Error(errors.into()),
+            Ok(()) => match place_words(options) {
+                Ok(puzzle) => BatchOutcome::Puzzle(puzzle),
+                Err(e) => BatchOutcome::Error(e.into()),
+            },
+        },
+    };
+
+    BatchItem { index, outcome }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(words: &[&str]) -> serde_json::Value {
+        serde_json::json!({ "size": 10, "words": words })
+    }
+
+    #[test]
+    fn errors_are_reported_per_item() {
+        let batch = BatchOptions {
+            puzzles: vec![
+                options(&["CLOUD", "RUST"]),
+                options(&[]),
+                options(&["WASM", "EDGE"]),
+                serde_json::json!({ "size": "large" }),
+            ],
+        };
+        let items = generate_batch(batch);
+
+        assert_eq!(
+            items.iter().map(|i| i.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert!(matches!(items[0].outcome, BatchOutcome::Puzzle(_)));
+        assert!(matches!(items[1].outcome, BatchOutcome::Error(_)));
+        assert!(matches!(items[2].outcome, BatchOutcome::Puzzle(_)));
+        assert!(matches!(&items[3].outcome, BatchOutcome::Error(e) if e.status == 400));
+
+        let line = items[1].to_ndjson_line();
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["index"], 1);
+        assert_eq!(json["error"]["errors"][0]["code"], "emptyWordList");
+    }
+
+    #[test]
+    fn large_batches_keep_their_order() {
+        let batch = BatchOptions {
+            puzzles: (0..50).map(|_| options(&["CLOUD", "RUST"])).collect(),
+        };
+        let indexes: Vec<usize> = generate_batch_iter(batch).map(|i| i.index).collect();
+        assert_eq!(indexes, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn batch_size() {
+        assert!(validate_batch(&BatchOptions::default()).is_err());
+
+        let batch = BatchOptions {
+            puzzles: (0..=MAX_BATCH_SIZE).map(|_| options(&["RUST"])).collect(),
+        };
+        assert_eq!(
+            validate_batch(&batch).unwrap_err(),
+            vec![ValidationError::BatchSizeOutOfRange {
+                count: MAX_BATCH_SIZE + 1,
+                min: 1,
+                max: MAX_BATCH_SIZE
+            }]
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{GeneratorError, ValidationError, MAX_SIZE, MIN_SIZE};
+
+// Naive crossword generator: words are added greedily, each new word has to
+// cross at least one word that is already on the grid.
+
+/// Character used in [`Crossword::grid`] for blocked cells
+pub const BLOCKED_CELL: char = '#';
+
+/// Minimum number of words necessary to build a crossword
+pub const MIN_CROSSWORD_WORDS: usize = 2;
+
+/// Maximum number of words in a crossword (every word is tried at every
+/// cell of the layout, so the effort grows quickly with the number of words)
+pub const MAX_CROSSWORD_WORDS: usize = 40;
+
+/// Number of layouts generated; the most compact one wins
+const ATTEMPTS: usize = 20;
+
+#[derive(Deserialize, Clone)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct CrosswordEntry {
+    pub word: String,
+    pub clue: String,
+}
+
+#[derive(Deserialize, Default)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct CrosswordOptions {
+    /// Maximum width and height of the resulting grid, defaults to [`MAX_SIZE`]
+    #[serde(default)]
+    pub max_size: Option<usize>,
+    pub entries: Vec<CrosswordEntry>,
+}
+
+impl CrosswordOptions {
+    pub fn max_size(&self) -> usize {
+        self.max_size.unwrap_or(MAX_SIZE)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct Clue {
+    pub number: u32,
+    pub word: String,
+    pub clue: String,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct Crossword {
+    /// Rows of the solved grid; blocked cells contain [`BLOCKED_CELL`]
+    pub grid: Vec<String>,
+    /// Clue number per cell (`None` if no word starts in the cell)
+    pub numbers: Vec<Vec<Option<u32>>>,
+    pub across: Vec<Clue>,
+    pub down: Vec<Clue>,
+    /// Words that could not be connected to the rest of the crossword
+    pub unplaced_words: Vec<String>,
+}
+
+/// Checks the crossword options and returns *all* issues found.
+pub fn validate_crossword(options: &CrosswordOptions) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    let max_size = options.max_size();
+    let size_is_valid = (MIN_SIZE..=MAX_SIZE).contains(&max_size);
+    if !size_is_valid {
+        errors.push(ValidationError::SizeOutOfRange {
+            dimension: "max_size",
+            size: max_size,
+            min: MIN_SIZE,
+            max: MAX_SIZE,
+        });
+    }
+
+    if options.entries.len() < MIN_CROSSWORD_WORDS {
+        errors.push(ValidationError::TooFewWords {
+            count: options.entries.len(),
+            min: MIN_CROSSWORD_WORDS,
+        });
+    } else if options.entries.len() > MAX_CROSSWORD_WORDS {
+        errors.push(ValidationError::TooManyWords {
+            count: options.entries.len(),
+            max: MAX_CROSSWORD_WORDS,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    for (index, entry) in options.entries.iter().enumerate() {
+        let word = &entry.word;
+        if word.is_empty() {
+            errors.push(ValidationError::EmptyWord { index });
+            continue;
+        }
+
+        if !word.chars().all(char::is_alphabetic) {
+            errors.push(ValidationError::InvalidCharacters { word: word.clone() });
+        }
+
+        let length = word.chars().count();
+        if size_is_valid && length > max_size {
+            errors.push(ValidationError::WordTooLong {
+                word: word.clone(),
+                length,
+                max: max_size,
+            });
+        }
+
+        if !seen.insert(word.to_uppercase()) {
+            errors.push(ValidationError::DuplicateWord { word: word.clone() });
+        }
+
+        if entry.clue.trim().is_empty() {
+            errors.push(ValidationError::MissingClue { word: word.clone() });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Word placed on the (unbounded) layout grid
+#[derive(Clone)]
+struct PlacedWord {
+    entry: usize,
+    row: i32,
+    col: i32,
+    across: bool,
+}
+
+/// Start row, start column, and direction (`true` = across) of a word
+type Placement = (i32, i32, bool);
+
+/// Cell of the (unbounded) layout grid
+#[derive(Clone, Copy)]
+struct LayoutCell {
+    letter: char,
+    across: bool,
+    down: bool,
+}
+
+#[derive(Clone, Default)]
+struct Layout {
+    cells: HashMap<(i32, i32), LayoutCell>,
+    words: Vec<PlacedWord>,
+}
+
+impl Layout {
+    /// Bounding box as (min row, min col, max row, max col)
+    fn bounds(&self) -> (i32, i32, i32, i32) {
+        self.cells.keys().fold(
+            (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+            |(r0, c0, r1, c1), &(r, c)| (r0.min(r), c0.min(c), r1.max(r), c1.max(c)),
+        )
+    }
+
+    fn area(&self) -> i32 {
+        let (r0, c0, r1, c1) = self.bounds();
+        (r1 - r0 + 1) * (c1 - c0 + 1)
+    }
+
+    /// Area of the bounding box if a word of the given length was added
+    fn area_with(&self, len: usize, (row, col, across): Placement) -> i32 {
+        let (r0, c0, r1, c1) = self.bounds();
+        let len = len as i32 - 1;
+        let (end_row, end_col) = if across {
+            (row, col + len)
+        } else {
+            (row + len, col)
+        };
+        (r1.max(end_row) - r0.min(row) + 1) * (c1.max(end_col) - c0.min(col) + 1)
+    }
+
+    fn is_free(&self, row: i32, col: i32) -> bool {
+        !self.cells.contains_key(&(row, col))
+    }
+
+    /// Returns the number of crossings if the word can be placed, `None` otherwise.
+    fn check(
+        &self,
+        word: &[char],
+        row: i32,
+        col: i32,
+        across: bool,
+        max_size: i32,
+    ) -> Option<usize> {
+        let (dr, dc) = if across { (0, 1) } else { (1, 0) };
+        let len = word.len() as i32;
+
+        // The cells directly before and after the word must be blocked
+        if !self.is_free(row - dr, col - dc) || !self.is_free(row + dr * len, col + dc * len) {
+            return None;
+        }
+
+        let mut crossings = 0;
+        for (i, letter) in word.iter().enumerate() {
+            let (r, c) = (row + dr * i as i32, col + dc * i as i32);
+            match self.cells.get(&(r, c)) {
+                Some(cell) => {
+                    let same_direction = if across { cell.across } else { cell.down };
+                    if cell.letter != *letter || same_direction {
+                        return None;
+                    }
+                    crossings += 1;
+                }
+                None => {
+                    // New letters must not touch neighbouring words sideways
+                    if !self.is_free(r + dc, c + dr) || !self.is_free(r - dc, c - dr) {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        if crossings == 0 {
+            return None;
+        }
+
+        let (r0, c0, r1, c1) = self.bounds();
+        let (end_row, end_col) = (row + dr * (len - 1), col + dc * (len - 1));
+        if r1.max(end_row) - r0.min(row) >= max_size || c1.max(end_col) - c0.min(col) >= max_size {
+            return None;
+        }
+
+        Some(crossings)
+    }
+
+    fn place(&mut self, entry: usize, word: &[char], row: i32, col: i32, across: bool) {
+        let (dr, dc) = if across { (0, 1) } else { (1, 0) };
+        for (i, letter) in word.iter().enumerate() {
+            let cell = self
+                .cells
+                .entry((row + dr * i as i32, col + dc * i as i32))
+                .or_insert(LayoutCell {
+                    letter: *letter,
+                    across: false,
+                    down: false,
+                });
+            if across {
+                cell.across = true;
+            } else {
+                cell.down = true;
+            }
+        }
+
+        self.words.push(PlacedWord {
+            entry,
+            row,
+            col,
+            across,
+        });
+    }
+
+    /// Finds the placement with the most crossings, preferring compact layouts.
+    fn best_placement(&self, word: &[char], max_size: i32) -> Option<Placement> {
+        let mut best: Option<((usize, i32), Placement)> = None;
+        for (&(r, c), cell) in self.cells.iter() {
+            for (i, letter) in word.iter().enumerate() {
+                if *letter != cell.letter {
+                    continue;
+                }
+
+                for across in [true, false] {
+                    let (row, col) = if across {
+                        (r, c - i as i32)
+                    } else {
+                        (r - i as i32, c)
+                    };
+                    if let Some(crossings) = self.check(word, row, col, across, max_size) {
+                        let score = (crossings, -self.area_with(word.len(), (row, col, across)));
+                        if best.as_ref().map(|b| score > b.0).unwrap_or(true) {
+                            best = Some((score, (row, col, across)));
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|b| b.1)
+    }
+}
+
+/// Builds a layout by adding words in the given order.
+fn build_layout(words: &[Vec<char>], order: &[usize], max_size: i32) -> Layout {
+    let mut layout = Layout::default();
+    let mut pending: Vec<usize> = order.to_vec();
+
+    let first = pending.remove(0);
+    layout.place(first, &words[first], 0, 0, true);
+
+    // Words that cannot be connected yet might fit after other words have been added
+    loop {
+        let mut progress = false;
+        pending.retain(
+            |&entry| match layout.best_placement(&words[entry], max_size) {
+                Some((row, col, across)) => {
+                    layout.place(entry, &words[entry], row, col, across);
+                    progress = true;
+                    false
+                }
+                None => true,
+            },
+        );
+
+        if !progress || pending.is_empty() {
+            break;
+        }
+    }
+
+    layout
+}
+
+/// Generates a compact crossword in which every word crosses at least one other word.
+pub fn generate_crossword(options: CrosswordOptions) -> Result<Crossword, GeneratorError> {
+    let mut rng = rand::thread_rng();
+    let max_size = options.max_size() as i32;
+    let words: Vec<Vec<char>> = options
+        .entries
+        .iter()
+        .map(|e| e.word.chars().flat_map(char::to_uppercase).collect())
+        .collect();
+
+    // Longest words first gives good results, shuffling equally long words adds variety
+    let mut best: Option<Layout> = None;
+    for _ in 0..ATTEMPTS {
+        let mut order: Vec<usize> = (0..words.len()).collect();
+        order.shuffle(&mut rng);
+        order.sort_by_key(|&i| std::cmp::Reverse(words[i].len()));
+
+        let layout = build_layout(&words, &order, max_size);
+        let is_better = match &best {
+            None => true,
+            Some(b) => (layout.words.len(), -layout.area()) > (b.words.len(), -b.area()),
+        };
+        if is_better {
+            best = Some(layout);
+        }
+    }
+
+    let layout = best.unwrap_or_default();
+    if layout.words.len() < MIN_CROSSWORD_WORDS {
+        return Err(GeneratorError::NoCrossingWords);
+    }
+
+    Ok(to_crossword(&layout, &words, &options.entries))
+}
+
+/// Converts the unbounded layout into a grid with numbering and clue lists.
+fn to_crossword(layout: &Layout, words: &[Vec<char>], entries: &[CrosswordEntry]) -> Crossword {
+    let (r0, c0, r1, c1) = layout.bounds();
+    let (height, width) = ((r1 - r0 + 1) as usize, (c1 - c0 + 1) as usize);
+
+    let mut grid = vec![vec![BLOCKED_CELL; width]; height];
+    for (&(r, c), cell) in layout.cells.iter() {
+        grid[(r - r0) as usize][(c - c0) as usize] = cell.letter;
+    }
+
+    // Number word starts in reading order
+    let mut starts: Vec<(usize, usize)> = layout
+        .words
+        .iter()
+        .map(|w| ((w.row - r0) as usize, (w.col - c0) as usize))
+        .collect();
+    starts.sort();
+    starts.dedup();
+    let mut numbers = vec![vec![None; width]; height];
+    for (i, &(row, col)) in starts.iter().enumerate() {
+        numbers[row][col] = Some(i as u32 + 1);
+    }
+
+    let mut across = Vec::new();
+    let mut down = Vec::new();
+    for placed in layout.words.iter() {
+        let (row, col) = ((placed.row - r0) as usize, (placed.col - c0) as usize);
+        let clue = Clue {
+            number: numbers[row][col].unwrap_or_default(),
+            word: words[placed.entry].iter().collect(),
+            clue: entries[placed.entry].clue.clone(),
+            row,
+            col,
+        };
+        if placed.across {
+            across.push(clue);
+        } else {
+            down.push(clue);
+        }
+    }
+    across.sort_by_key(|c| c.number);
+    down.sort_by_key(|c| c.number);
+
+    let placed: HashSet<usize> = layout.words.iter().map(|w| w.entry).collect();
+    Crossword {
+        grid: grid.iter().map(|row| row.iter().collect()).collect(),
+        numbers,
+        across,
+        down,
+        unplaced_words: (0..entries.len())
+            .filter(|i| !placed.contains(i))
+            .map(|i| entries[i].word.clone())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(words: &[&str]) -> CrosswordOptions {
+        CrosswordOptions {
+            entries: words
+                .iter()
+                .map(|w| CrosswordEntry {
+                    word: w.to_string(),
+                    clue: format!("Clue for {w}"),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn words_cross_each_other() {
+        let crossword =
+            generate_crossword(options(&["SERVERLESS", "CLOUD", "RUST", "WASM", "EDGE"])).unwrap();
+        assert!(crossword.unplaced_words.is_empty());
+        assert_eq!(crossword.across.len() + crossword.down.len(), 5);
+
+        // Every clue must match the letters in the grid
+        let grid: Vec<Vec<char>> = crossword.grid.iter().map(|r| r.chars().collect()).collect();
+        for (clues, across) in [(&crossword.across, true), (&crossword.down, false)] {
+            for clue in clues {
+                assert_eq!(crossword.numbers[clue.row][clue.col], Some(clue.number));
+                for (i, c) in clue.word.chars().enumerate() {
+                    let cell = if across {
+                        grid[clue.row][clue.col + i]
+                    } else {
+                        grid[clue.row + i][clue.col]
+                    };
+                    assert_eq!(cell, c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn words_without_common_letters_are_not_placed() {
+        let crossword = generate_crossword(options(&["RUST", "RUSTY", "XXX"])).unwrap();
+        assert_eq!(crossword.unplaced_words, vec!["XXX".to_string()]);
+        assert_eq!(
+            generate_crossword(options(&["ABC", "XYZ"])).unwrap_err(),
+            GeneratorError::NoCrossingWords
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let mut invalid = options(&["RUST"]);
+        invalid.entries[0].clue = " ".to_string();
+        assert_eq!(
+            validate_crossword(&invalid).unwrap_err(),
+            vec![
+                ValidationError::TooFewWords { count: 1, min: 2 },
+                ValidationError::MissingClue {
+                    word: "RUST".to_string()
+                },
+            ]
+        );
+
+        let words: Vec<String> = (0..=MAX_CROSSWORD_WORDS)
+            .map(|i| [b'A' + (i / 26) as u8, b'A' + (i % 26) as u8])
+            .map(|letters| String::from_utf8(letters.to_vec()).unwrap())
+            .collect();
+        let mut too_large = options(&words.iter().map(String::as_str).collect::<Vec<_>>());
+        too_large.max_size = Some(1);
+        assert_eq!(
+            validate_crossword(&too_large).unwrap_err()[..2],
+            [
+                ValidationError::SizeOutOfRange {
+                    dimension: "max_size",
+                    size: 1,
+                    min: MIN_SIZE,
+                    max: MAX_SIZE
+                },
+                ValidationError::TooManyWords {
+                    count: MAX_CROSSWORD_WORDS + 1,
+                    max: MAX_CROSSWORD_WORDS
+                },
+            ]
+        );
+    }
+}
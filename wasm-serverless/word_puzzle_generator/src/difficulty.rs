@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{solve, Direction, Occurrence, Puzzle, WordPlacement, MASKED_CELL};
+
+/// Number of puzzles generated when trying to hit a target difficulty
+pub(crate) const DIFFICULTY_ATTEMPTS: usize = 10;
+
+/// Scores below this value are considered easy
+const MEDIUM_THRESHOLD: f64 = 30f64;
+
+/// Scores from this value on are considered hard
+const HARD_THRESHOLD: f64 = 60f64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_score(score: f64) -> Self {
+        if score < MEDIUM_THRESHOLD {
+            Difficulty::Easy
+        } else if score < HARD_THRESHOLD {
+            Difficulty::Medium
+        } else {
+            Difficulty::Hard
+        }
+    }
+
+    /// Score in the middle of the difficulty's range
+    pub fn target_score(self) -> f64 {
+        match self {
+            Difficulty::Easy => MEDIUM_THRESHOLD / 2f64,
+            Difficulty::Medium => (MEDIUM_THRESHOLD + HARD_THRESHOLD) / 2f64,
+            Difficulty::Hard => (HARD_THRESHOLD + 100f64) / 2f64,
+        }
+    }
+}
+
+/// Estimated difficulty of a puzzle.
+///
+/// All factors are in the range 0 (easy) to 1 (hard), `score` is their
+/// weighted sum scaled to 0..=100.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultyScore {
+    pub score: f64,
+    pub level: Difficulty,
+    /// How hard the directions are to spot (horizontal < vertical < diagonal, reversed is harder)
+    pub direction_mix: f64,
+    /// Share of words written backwards
+    pub reversed_words: f64,
+    /// Share of letters used by more than one word
+    pub overlaps: f64,
+    /// Prefixes of words in the grid that are not part of the word (near misses)
+    pub decoys: f64,
+    /// Share of usable cells not used by words
+    pub sparsity: f64,
+}
+
+const DIRECTION_MIX_WEIGHT: f64 = 0.35f64;
+const REVERSED_WEIGHT: f64 = 0.2f64;
+const OVERLAP_WEIGHT: f64 = 0.1f64;
+const DECOY_WEIGHT: f64 = 0.2f64;
+const SPARSITY_WEIGHT: f64 = 0.15f64;
+
+/// Estimates how hard it is to find all words of the puzzle.
+pub fn estimate_difficulty(puzzle: &Puzzle) -> DifficultyScore {
+    let grid: Vec<Vec<char>> = puzzle.grid.iter().map(|r| r.chars().collect()).collect();
+    let placements = &puzzle.placements;
+    let words = placements.len().max(1) as f64;
+
+    let direction_mix = placements
+        .iter()
+        .map(|p| {
+            let direction = match p.direction {
+                Direction::Horizontal => 0f64,
+                Direction::Vertical => 0.35f64,
+                Direction::Diagonal => 0.7f64,
+            };
+            direction + if p.reversed { 0.3f64 } else { 0f64 }
+        })
+        .sum::<f64>()
+        / words;
+
+    let reversed_words = placements.iter().filter(|p| p.reversed).count() as f64 / words;
+
+    // Count how many words use each cell
+    let mut usage: HashMap<(usize, usize), usize> = HashMap::new();
+    for cell in placements.iter().flat_map(|p| p.cells()) {
+        *usage.entry(cell).or_default() += 1;
+    }
+    let letters = usage.values().sum::<usize>().max(1) as f64;
+    let shared = usage.values().filter(|u| **u > 1).count() as f64;
+    // Even in hard puzzles only a few letters are shared, so small shares count a lot
+    let overlaps = (shared / letters * 3f64).min(1f64);
+
+    let prefixes: Vec<String> = placements
+        .iter()
+        .filter_map(|p| decoy_prefix(&p.word))
+        .collect();
+    let decoy_count = solve(&puzzle.grid, &prefixes)
+        .words
+        .iter()
+        .flat_map(|w| w.occurrences.iter())
+        .filter(|o| !is_start_of_placement(o, placements))
+        .count();
+    let decoys = (decoy_count as f64 / words).min(1f64);
+
+    let usable = grid.iter().flatten().filter(|c| **c != MASKED_CELL).count();
+    let sparsity = if usable == 0 {
+        0f64
+    } else {
+        1f64 - usage.len() as f64 / usable as f64
+    };
+
+    let score = 100f64
+        * (DIRECTION_MIX_WEIGHT * direction_mix
+            + REVERSED_WEIGHT * reversed_words
+            + OVERLAP_WEIGHT * overlaps
+            + DECOY_WEIGHT * decoys
+            + SPARSITY_WEIGHT * sparsity);
+
+    DifficultyScore {
+        score,
+        level: Difficulty::from_score(score),
+        direction_mix,
+        reversed_words,
+        overlaps,
+        decoys,
+        sparsity,
+    }
+}
+
+/// Near-miss prefix of a word used as decoy (all letters except the last one)
+pub(crate) fn decoy_prefix(word: &str) -> Option<String> {
+    let len = word.chars().count();
+    (len >= 4).then(|| word.chars().take(len - 1).collect())
+}
+
+/// Checks whether the occurrence is the beginning of a placed word
+fn is_start_of_placement(occurrence: &Occurrence, placements: &[WordPlacement]) -> bool {
+    placements.iter().any(|p| {
+        let (row_step, col_step) = p.direction.delta();
+        let sign = if p.reversed { -1 } else { 1 };
+        *occurrence
+            == Occurrence {
+                row: p.row,
+                col: p.col,
+                row_step: row_step as isize * sign,
+                col_step: col_step as isize * sign,
+            }
+    })
+}
+
+/// Controls direction choice and filler during generation
+pub(crate) struct DirectionProfile {
+    /// Weights for [`Direction::ALL`]
+    weights: [u32; 3],
+    pub reversed_probability: f64,
+    pub decoys: bool,
+}
+
+impl DirectionProfile {
+    pub fn for_difficulty(difficulty: Option<Difficulty>) -> Self {
+        let (weights, reversed_probability, decoys) = match difficulty {
+            None => ([1, 1, 1], 0f64, false),
+            Some(Difficulty::Easy) => ([3, 0, 1], 0f64, false),
+            Some(Difficulty::Medium) => ([1, 2, 1], 0.4f64, false),
+            Some(Difficulty::Hard) => ([1, 4, 1], 0.75f64, true),
+        };
+
+        Self {
+            weights,
+            reversed_probability,
+            decoys,
+        }
+    }
+
+    pub fn choose_direction(&self, rng: &mut impl Rng) -> Direction {
+        let total: u32 = self.weights.iter().sum();
+        let mut value = rng.gen_range(0..total);
+        for (direction, weight) in Direction::ALL.iter().zip(self.weights) {
+            if value < weight {
+                return *direction;
+            }
+            value -= weight;
+        }
+
+        unreachable!("value is lower than the sum of weights")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{place_words, GeneratorOptions};
+
+    fn placement(word: &str, row: usize, col: usize, direction: Direction) -> WordPlacement {
+        WordPlacement {
+            word: word.to_string(),
+            row,
+            col,
+            direction,
+            reversed: false,
+        }
+    }
+
+    #[test]
+    fn easy_puzzle() {
+        let puzzle = Puzzle {
+            grid: vec!["RUST".to_string(), "WASM".to_string()],
+            words: vec!["RUST".to_string(), "WASM".to_string()],
+            placements: vec![
+                placement("RUST", 0, 0, Direction::Horizontal),
+                placement("WASM", 1, 0, Direction::Horizontal),
+            ],
+        };
+        let estimate = estimate_difficulty(&puzzle);
+        assert_eq!(estimate.score, 0f64);
+        assert_eq!(estimate.level, Difficulty::Easy);
+    }
+
+    #[test]
+    fn decoys_and_reversed_words() {
+        let mut reversed = placement("RUST", 0, 3, Direction::Horizontal);
+        reversed.reversed = true;
+        let puzzle = Puzzle {
+            grid: vec!["TSUR".to_string(), "RUSX".to_string()],
+            words: vec!["RUST".to_string()],
+            placements: vec![reversed],
+        };
+        let estimate = estimate_difficulty(&puzzle);
+        assert_eq!(estimate.reversed_words, 1f64);
+        assert_eq!(estimate.decoys, 1f64);
+        assert_eq!(estimate.sparsity, 0.5f64);
+    }
+
+    #[test]
+    fn target_difficulty() {
+        let words = [
+            "SERVERLESS",
+            "COMPUTING",
+            "SOFTWARE",
+            "DEVOPS",
+            "CLOUD",
+            "SNAKE",
+            "SCHOOL",
+            "BANANA",
+        ];
+        for target in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let puzzle = place_words(GeneratorOptions {
+                size: 12,
+                words: words.iter().map(|w| w.to_string()).collect(),
+                target_difficulty: Some(target),
+                ..Default::default()
+            })
+            .unwrap();
+            assert_eq!(estimate_difficulty(&puzzle).level, target);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+use crate::ValidationError;
+
+/// Errors that can occur while generating a puzzle from valid options.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GeneratorError {
+    #[error("hidden message has {letters} letters, but only {free_cells} cells are left after placing the words")]
+    HiddenMessageTooLong { letters: usize, free_cells: usize },
+    #[error("no two words share a letter, so they cannot be combined into a crossword")]
+    NoCrossingWords,
+    #[error("word '{word}' occurs more than once and cannot be made unique")]
+    AmbiguousWord { word: String },
+    #[error("blocked word '{word}' is formed by the placed words or the hidden message")]
+    BlockedWordInGrid { word: String },
+    #[error("could not generate a grid in which every word occurs exactly once")]
+    UniqueSolutionNotFound,
+    /// Options that [`crate::validate`] would have rejected
+    #[error(transparent)]
+    InvalidOptions(#[from] ValidationError),
+    #[error("no words of the theme match the requested word lengths")]
+    NoMatchingThemeWords,
+}
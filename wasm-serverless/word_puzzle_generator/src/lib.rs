@@ -2,45 +2,310 @@ use std::collections::HashSet;
 
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use uniqueness::ensure_unique;
+
+pub mod api;
+mod batch;
+mod crossword;
+mod difficulty;
+mod error;
+mod mask;
+#[cfg(feature = "render")]
+pub mod render;
+mod solver;
+mod themes;
+mod uniqueness;
+mod validation;
+
+pub use batch::*;
+pub use crossword::*;
+pub use difficulty::*;
+pub use error::*;
+pub use mask::*;
+pub use solver::*;
+pub use themes::*;
+pub use validation::*;
 
 // Naive word puzzle generator
 
-#[derive(Deserialize)]
+/// Number of times a puzzle is generated again if it cannot be made unique
+const GENERATION_ATTEMPTS: usize = 5;
+
+/// Character used for cells that have not been filled yet
+const EMPTY_CELL: char = '.';
+
+/// Character used in [`Puzzle::grid`] for cells excluded by the mask.
+///
+/// A space, like in the ASCII art of [`MaskSpec::Ascii`] (where `#` marks
+/// usable cells).
+pub const MASKED_CELL: char = ' ';
+
+#[derive(Deserialize, Default)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct GeneratorOptions {
+    /// Size of a square grid, used if `width` or `height` are not set
+    #[serde(default)]
     pub size: usize,
+    #[serde(default)]
+    pub width: Option<usize>,
+    #[serde(default)]
+    pub height: Option<usize>,
+    /// Optional mask restricting which cells can be used
+    #[serde(default)]
+    pub mask: Option<MaskSpec>,
+    /// Optional secret phrase spelled by the leftover letters (read row by row)
+    /// once all words have been found. Only letters are used, everything else
+    /// (e.g. spaces) is ignored.
+    #[serde(default)]
+    pub hidden_message: Option<String>,
+    /// Optional difficulty the generated puzzle should have
+    #[serde(default)]
+    pub target_difficulty: Option<Difficulty>,
+    /// Words that must not appear anywhere in the grid (e.g. offensive words)
+    #[serde(default)]
+    pub blocklist: Vec<String>,
+    /// Bundled theme to pick words from instead of passing `words` (see [`available_themes`])
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Language of the theme, defaults to [`DEFAULT_LANGUAGE`]
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Number of words picked from the theme, defaults to [`DEFAULT_THEME_WORD_COUNT`]
+    #[serde(default)]
+    pub word_count: Option<usize>,
+    /// Shortest word picked from the theme, defaults to [`DEFAULT_MIN_WORD_LENGTH`]
+    #[serde(default)]
+    pub min_word_length: Option<usize>,
+    /// Longest word picked from the theme, defaults to the grid size
+    #[serde(default)]
+    pub max_word_length: Option<usize>,
+    #[serde(default)]
     pub words: Vec<String>,
 }
 
-#[derive(Serialize)]
+impl GeneratorOptions {
+    /// Width of the grid. Falls back to the mask's width (ASCII art masks) and `size`.
+    pub fn width(&self) -> usize {
+        self.width
+            .or_else(|| self.mask.as_ref().and_then(|m| m.dimensions()).map(|d| d.0))
+            .unwrap_or(self.size)
+    }
+
+    /// Height of the grid. Falls back to the mask's height (ASCII art masks) and `size`.
+    pub fn height(&self) -> usize {
+        self.height
+            .or_else(|| self.mask.as_ref().and_then(|m| m.dimensions()).map(|d| d.1))
+            .unwrap_or(self.size)
+    }
+
+    pub fn language(&self) -> &str {
+        self.language.as_deref().unwrap_or(DEFAULT_LANGUAGE)
+    }
+
+    fn mask(&self) -> Mask {
+        let (width, height) = (self.width(), self.height());
+        self.mask
+            .as_ref()
+            .map(|m| m.to_mask(width, height))
+            .unwrap_or_else(|| Mask::full(width, height))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
 pub struct Puzzle {
+    /// Rows of the grid; cells excluded by the mask contain [`MASKED_CELL`] (a space)
     pub grid: Vec<String>,
     pub words: Vec<String>,
+    /// Position of every placed word (the answer key)
+    pub placements: Vec<WordPlacement>,
+}
+
+/// Position of a word in the grid
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct WordPlacement {
+    pub word: String,
+    /// Row of the first letter
+    pub row: usize,
+    /// Column of the first letter
+    pub col: usize,
+    pub direction: Direction,
+    /// `true` if the word is written backwards, i.e. against `direction`
+    #[serde(default)]
+    pub reversed: bool,
+}
+
+impl WordPlacement {
+    /// Row and column of every letter of the word (in the order of the word)
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (row_step, col_step) = self.direction.delta();
+        (0..self.word.chars().count()).map(move |i| {
+            if self.reversed {
+                (self.row - i * row_step, self.col - i * col_step)
+            } else {
+                (self.row + i * row_step, self.col + i * col_step)
+            }
+        })
+    }
+
+    /// Row and column of the last letter
+    pub fn end(&self) -> (usize, usize) {
+        self.cells().last().unwrap_or((self.row, self.col))
+    }
+}
+
+/// Directions in which words are placed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    Horizontal,
+    Diagonal,
+    Vertical,
+}
+
+impl Direction {
+    const ALL: [Direction; 3] = [
+        Direction::Horizontal,
+        Direction::Diagonal,
+        Direction::Vertical,
+    ];
+
+    /// Row and column step when moving from one letter to the next
+    pub fn delta(self) -> (usize, usize) {
+        match self {
+            Direction::Horizontal => (0, 1),
+            Direction::Diagonal => (1, 1),
+            Direction::Vertical => (1, 0),
+        }
+    }
+}
+
+pub fn place_words(mut options: GeneratorOptions) -> Result<Puzzle, GeneratorError> {
+    // Pick words from the theme if no words are given
+    if let (true, Some(theme)) = (options.words.is_empty(), options.theme.as_deref()) {
+        let theme =
+            find_theme(theme, options.language()).ok_or_else(|| ValidationError::UnknownTheme {
+                theme: theme.to_string(),
+                language: options.language().to_string(),
+            })?;
+        let longest = options.width().max(options.height());
+        let selection = ThemeSelection {
+            count: options.word_count.unwrap_or(DEFAULT_THEME_WORD_COUNT),
+            min_length: options.min_word_length.unwrap_or(DEFAULT_MIN_WORD_LENGTH),
+            max_length: options.max_word_length.unwrap_or(longest).min(longest),
+            cells: options.mask().usable_cells(),
+            blocklist: &options.blocklist,
+        };
+        options.words = select_words(theme, &selection, &mut rand::thread_rng());
+        if options.words.is_empty() {
+            return Err(GeneratorError::NoMatchingThemeWords);
+        }
+    }
+
+    // Words are placed in uppercase like the filler, lowercase letters would give them away
+    for word in options.words.iter_mut() {
+        *word = word.to_uppercase();
+    }
+
+    // Sort words by length, they are processed longest first
+    options.words.sort_by_key(|w| w.len());
+
+    let Some(target) = options.target_difficulty else {
+        return generate_unique(&options);
+    };
+
+    // Generation is random, so we generate a few puzzles and keep
+    // the one closest to the requested difficulty.
+    let mut best: Option<(f64, Puzzle)> = None;
+    for _ in 0..DIFFICULTY_ATTEMPTS {
+        let puzzle = generate_unique(&options)?;
+        let estimate = estimate_difficulty(&puzzle);
+        let distance = (estimate.score - target.target_score()).abs();
+        if estimate.level == target {
+            return Ok(puzzle);
+        }
+
+        if best.as_ref().map(|b| distance < b.0).unwrap_or(true) {
+            best = Some((distance, puzzle));
+        }
+    }
+
+    Ok(best.map(|b| b.1).expect("at least one attempt"))
+}
+
+/// Generates a puzzle in which every word occurs exactly once.
+///
+/// Filler cells can be re-rolled, but if placed words accidentally form another
+/// word (or a blocked word), the whole puzzle has to be generated again.
+fn generate_unique(options: &GeneratorOptions) -> Result<Puzzle, GeneratorError> {
+    let mut result = generate(options);
+    for _ in 1..GENERATION_ATTEMPTS {
+        match result {
+            Err(GeneratorError::AmbiguousWord { .. })
+            | Err(GeneratorError::BlockedWordInGrid { .. })
+            | Err(GeneratorError::UniqueSolutionNotFound) => result = generate(options),
+            _ => break,
+        }
+    }
+
+    result
 }
 
-pub fn place_words(mut options: GeneratorOptions) -> Puzzle {
+/// Generates a single puzzle, `options.words` must be sorted by length.
+fn generate(options: &GeneratorOptions) -> Result<Puzzle, GeneratorError> {
     let mut rng = rand::thread_rng();
+    let (width, height) = (options.width(), options.height());
+    let profile = DirectionProfile::for_difficulty(options.target_difficulty);
 
-    // Create empty grid
-    let mut grid = vec![vec!['.'; options.size]; options.size];
+    // Create empty grid, masked cells are blocked right from the start
+    let mask = options.mask();
+    let mut grid: Vec<Vec<char>> = (0..height)
+        .map(|row| {
+            (0..width)
+                .map(|col| {
+                    if mask.is_usable(row, col) {
+                        EMPTY_CELL
+                    } else {
+                        MASKED_CELL
+                    }
+                })
+                .collect()
+        })
+        .collect();
 
-    // Remember unplaced words
+    // Remember placed and unplaced words
+    let mut placements = Vec::new();
     let mut unplaced_words = HashSet::new();
 
-    // Sort words by length, process longest first
-    options.words.sort_by_key(|w| w.len());
     for word in options.words.iter().rev() {
         let mut placed = false;
 
         // In this naive implementation, we try to place a word 100 times before giving up.
         for _ in 0..100 {
-            let direction = rng.gen_range(0..=2); // 0: horizontal, 1: diagonal, 2: vertical
-            placed = match direction {
-                0 => place_word_horizontally(word, &mut grid),
-                1 => place_word_diagonally(word, &mut grid),
-                2 => place_word_vertically(word, &mut grid),
-                _ => panic!("Invalid direction"),
+            let direction = profile.choose_direction(&mut rng);
+            let reversed = rng.gen_bool(profile.reversed_probability);
+            let letters: String = if reversed {
+                word.chars().rev().collect()
+            } else {
+                word.clone()
             };
-            if placed {
+            if let Some((row, col)) = place_word(&letters, direction, &mut grid) {
+                let mut placement = WordPlacement {
+                    word: word.clone(),
+                    row,
+                    col,
+                    direction,
+                    reversed: false,
+                };
+                if reversed {
+                    // The word starts at the end of the placed letters
+                    (placement.row, placement.col) = placement.end();
+                    placement.reversed = true;
+                }
+                placements.push(placement);
+                placed = true;
                 break;
             }
         }
@@ -51,8 +316,21 @@ pub fn place_words(mut options: GeneratorOptions) -> Puzzle {
         }
     }
 
-    fill_remaining_spots(&mut grid);
-    Puzzle {
+    // Near-miss decoys would change the hidden message, so they are only used without one
+    if profile.decoys && options.hidden_message.is_none() {
+        place_decoys(&placements, &mut grid, &mut rng);
+    }
+
+    let message_cells = fill_remaining_spots(&mut grid, options.hidden_message.as_deref())?;
+    ensure_unique(
+        &mut grid,
+        &placements,
+        &options.blocklist,
+        &message_cells,
+        &mut rng,
+    )?;
+
+    Ok(Puzzle {
         grid: grid
             .iter()
             .map(|row| row.iter().collect::<String>())
@@ -63,96 +341,306 @@ pub fn place_words(mut options: GeneratorOptions) -> Puzzle {
             .filter(|w| !unplaced_words.contains(w))
             .cloned()
             .collect(),
+        placements,
+    })
+}
+
+/// Places prefixes of the words (e.g. `SERVERLES` for `SERVERLESS`) into
+/// empty cells to make the puzzle harder.
+fn place_decoys(placements: &[WordPlacement], grid: &mut [Vec<char>], rng: &mut impl Rng) {
+    for placement in placements.iter() {
+        let Some(decoy) = decoy_prefix(&placement.word) else {
+            continue;
+        };
+
+        let direction = Direction::ALL[rng.gen_range(0..Direction::ALL.len())];
+        let letters: String = if rng.gen_bool(0.5f64) {
+            decoy.chars().rev().collect()
+        } else {
+            decoy.clone()
+        };
+
+        // Decoys must only use empty cells, otherwise they could complete a word
+        let height = grid.len();
+        let width = grid.first().map(|r| r.len()).unwrap_or(0);
+        let (row_step, col_step) = direction.delta();
+        let len = letters.chars().count();
+        if (len - 1) * row_step >= height || (len - 1) * col_step >= width {
+            continue;
+        }
+        for _ in 0..10 {
+            let row = rng.gen_range(0..height - (len - 1) * row_step);
+            let col = rng.gen_range(0..width - (len - 1) * col_step);
+            if (0..len).all(|i| grid[row + i * row_step][col + i * col_step] == EMPTY_CELL) {
+                for (i, c) in letters.chars().enumerate() {
+                    grid[row + i * row_step][col + i * col_step] = c;
+                }
+                break;
+            }
+        }
     }
 }
 
-fn place_word_horizontally(word: &str, grid: &mut Vec<Vec<char>>) -> bool {
+/// Tries to place the word at a random position, returns the position on success.
+fn place_word(word: &str, direction: Direction, grid: &mut [Vec<char>]) -> Option<(usize, usize)> {
     let mut rng = rand::thread_rng();
     let max_tries = 10;
 
+    let height = grid.len();
+    let width = grid.first().map(|r| r.len()).unwrap_or(0);
+    let (row_step, col_step) = direction.delta();
+
+    // Words that do not fit into the grid in this direction can never be placed
+    let word_len = word.chars().count();
+    if word_len == 0 || (word_len - 1) * row_step >= height || (word_len - 1) * col_step >= width {
+        return None;
+    }
+
     for _ in 0..max_tries {
-        let row = rng.gen_range(0..grid.len());
-        let col = rng.gen_range(0..=grid.len() - word.len());
+        let row = rng.gen_range(0..height - (word_len - 1) * row_step);
+        let col = rng.gen_range(0..width - (word_len - 1) * col_step);
 
-        if can_place_word_horizontally(word, row, col, grid) {
+        if can_place_word(word, row, col, direction, grid) {
             for (i, c) in word.chars().enumerate() {
-                grid[row][col + i] = c;
+                grid[row + i * row_step][col + i * col_step] = c;
             }
-            return true;
+            return Some((row, col));
         }
     }
 
-    false
+    None
 }
 
-fn can_place_word_horizontally(word: &str, row: usize, col: usize, grid: &[Vec<char>]) -> bool {
-    word.chars()
-        .enumerate()
-        .all(|(i, c)| grid[row][col + i] == '.' || grid[row][col + i] == c)
+fn can_place_word(
+    word: &str,
+    row: usize,
+    col: usize,
+    direction: Direction,
+    grid: &[Vec<char>],
+) -> bool {
+    let (row_step, col_step) = direction.delta();
+    word.chars().enumerate().all(|(i, c)| {
+        let cell = grid[row + i * row_step][col + i * col_step];
+        cell == EMPTY_CELL || cell == c
+    })
 }
 
-fn place_word_diagonally(word: &str, grid: &mut Vec<Vec<char>>) -> bool {
+/// Fills all empty cells of the grid.
+///
+/// Without a hidden message, random letters A-Z are used. With a hidden message,
+/// its letters are placed in reading order (row by row) into the empty cells.
+/// If there are more empty cells than letters, the remaining ones get random letters.
+/// Returns the cells containing letters of the hidden message.
+fn fill_remaining_spots(
+    grid: &mut [Vec<char>],
+    hidden_message: Option<&str>,
+) -> Result<Vec<(usize, usize)>, GeneratorError> {
     let mut rng = rand::thread_rng();
-    let max_tries = 10;
 
-    for _ in 0..max_tries {
-        let row = rng.gen_range(0..=grid.len() - word.len());
-        let col = rng.gen_range(0..=grid.len() - word.len());
+    let message: Vec<char> = hidden_message
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_uppercase)
+        .collect();
+    let free_cells = grid.iter().flatten().filter(|c| **c == EMPTY_CELL).count();
+    if message.len() > free_cells {
+        return Err(GeneratorError::HiddenMessageTooLong {
+            letters: message.len(),
+            free_cells,
+        });
+    }
 
-        if can_place_word_diagonally(word, row, col, grid) {
-            for (i, c) in word.chars().enumerate() {
-                grid[row + i][col + i] = c;
+    let mut message_cells = Vec::with_capacity(message.len());
+    let mut message = message.into_iter();
+    for (row_index, row) in grid.iter_mut().enumerate() {
+        for (col_index, cell) in row.iter_mut().enumerate() {
+            if *cell == EMPTY_CELL {
+                *cell = match message.next() {
+                    Some(letter) => {
+                        message_cells.push((row_index, col_index));
+                        letter
+                    }
+                    None => random_letter(&mut rng),
+                };
             }
-            return true;
         }
     }
 
-    false
+    Ok(message_cells)
 }
 
-fn can_place_word_diagonally(word: &str, row: usize, col: usize, grid: &[Vec<char>]) -> bool {
-    word.chars()
-        .enumerate()
-        .all(|(i, c)| grid[row + i][col + i] == '.' || grid[row + i][col + i] == c)
+/// Random filler letter A-Z
+pub(crate) fn random_letter(rng: &mut impl Rng) -> char {
+    let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    alphabet
+        .chars()
+        .nth(rng.gen_range(0..alphabet.len()))
+        .unwrap()
 }
 
-fn place_word_vertically(word: &str, grid: &mut Vec<Vec<char>>) -> bool {
-    let mut rng = rand::thread_rng();
-    let max_tries = 10;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for _ in 0..max_tries {
-        let row = rng.gen_range(0..=grid.len() - word.len());
-        let col = rng.gen_range(0..grid.len());
+    #[test]
+    fn words_are_placed_in_uppercase() {
+        let puzzle = place_words(GeneratorOptions {
+            size: 8,
+            words: vec!["rust".to_string(), "Übung".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(puzzle.words, ["RUST", "ÜBUNG"]);
+        assert!(puzzle
+            .grid
+            .iter()
+            .all(|row| !row.chars().any(char::is_lowercase)));
+    }
 
-        if can_place_word_vertically(word, row, col, grid) {
-            for (i, c) in word.chars().enumerate() {
-                grid[row + i][col] = c;
+    #[test]
+    fn rectangular_grid() {
+        let puzzle = place_words(GeneratorOptions {
+            width: Some(12),
+            height: Some(5),
+            words: vec!["SERVERLESS".to_string(), "RUST".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(puzzle.grid.len(), 5);
+        assert!(puzzle.grid.iter().all(|row| row.chars().count() == 12));
+        assert_eq!(puzzle.words.len(), 2);
+
+        // The answer key must point to the letters of the words
+        let grid: Vec<Vec<char>> = puzzle.grid.iter().map(|r| r.chars().collect()).collect();
+        for placement in puzzle.placements.iter() {
+            for ((row, col), c) in placement.cells().zip(placement.word.chars()) {
+                assert_eq!(grid[row][col], c);
             }
-            return true;
         }
     }
 
-    false
-}
+    #[test]
+    fn masked_cells_stay_masked() {
+        let puzzle = place_words(GeneratorOptions {
+            mask: Some(MaskSpec::Ascii(vec![
+                "#####".to_string(),
+                "#...#".to_string(),
+                "#####".to_string(),
+            ])),
+            words: vec!["CLOUD".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(puzzle.grid.len(), 3);
+        assert_eq!(&puzzle.grid[1][1..4], MASKED_CELL.to_string().repeat(3));
+        assert!(puzzle.grid[0].chars().all(|c| c.is_ascii_uppercase()));
+    }
 
-fn can_place_word_vertically(word: &str, row: usize, col: usize, grid: &[Vec<char>]) -> bool {
-    word.chars()
-        .enumerate()
-        .all(|(i, c)| grid[row + i][col] == '.' || grid[row + i][col] == c)
-}
+    #[test]
+    fn words_are_unique_and_blocked_words_missing() {
+        let words: Vec<String> = ["ABBA", "BAB", "ABA", "BAA"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let blocklist = vec!["AAA".to_string(), "XX".to_string()];
+        let mut successes = 0;
+        for _ in 0..10 {
+            let result = place_words(GeneratorOptions {
+                size: 6,
+                words: words.clone(),
+                blocklist: blocklist.clone(),
+                ..Default::default()
+            });
 
-fn fill_remaining_spots(grid: &mut [Vec<char>]) {
-    let mut rng = rand::thread_rng();
-    let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+            // Short words can form each other, generation may fail sometimes
+            let Ok(puzzle) = result else { continue };
+            successes += 1;
+            let solution = solve(&puzzle.grid, &puzzle.words);
+            assert!(solution.is_unique());
+            assert!(solve(&puzzle.grid, &blocklist)
+                .words
+                .iter()
+                .all(|w| w.occurrences.is_empty()));
+        }
+        assert!(successes > 0);
+    }
+
+    #[test]
+    fn words_within_other_words() {
+        for words in [&["SUN", "SUNDAY"][..], &["CAT", "CATS", "DOG"]] {
+            let options = || GeneratorOptions {
+                size: 10,
+                words: words.iter().map(|w| w.to_string()).collect(),
+                ..Default::default()
+            };
+            assert!(validate(&options()).is_ok());
 
-    for row in grid.iter_mut() {
-        for cell in row.iter_mut() {
-            if *cell == '.' {
-                *cell = alphabet
-                    .chars()
-                    .nth(rng.gen_range(0..alphabet.len()))
-                    .unwrap();
+            for _ in 0..50 {
+                let puzzle = place_words(options()).unwrap();
+
+                // The occurrence that counts is the one in the answer key
+                let solution = solve(&puzzle.grid, &puzzle.words);
+                assert!(solution.is_unique());
+                for word in solution.words.iter() {
+                    let placement = puzzle.placements.iter().find(|p| p.word == word.word);
+                    let mut placed: Vec<_> = placement.unwrap().cells().collect();
+                    let cells: Vec<_> = word.occurrences[0].cells(placed.len()).collect();
+                    if cells != placed {
+                        placed.reverse();
+                    }
+                    assert_eq!(cells, placed);
+                }
             }
         }
     }
+
+    #[test]
+    fn words_from_theme() {
+        let puzzle = place_words(GeneratorOptions {
+            size: 12,
+            theme: Some("animals".to_string()),
+            word_count: Some(8),
+            max_word_length: Some(7),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!puzzle.words.is_empty());
+        assert!(puzzle.words.iter().all(|w| (3..=7).contains(&w.len())));
+
+        assert_eq!(
+            place_words(GeneratorOptions {
+                size: 12,
+                theme: Some("animals".to_string()),
+                min_word_length: Some(12),
+                ..Default::default()
+            })
+            .unwrap_err(),
+            GeneratorError::NoMatchingThemeWords
+        );
+    }
+
+    #[test]
+    fn hidden_message_in_reading_order() {
+        let mut grid = vec![
+            vec!['R', 'U', 'S', 'T'],
+            vec!['.', '.', MASKED_CELL, '.'],
+            vec!['.', 'A', '.', '.'],
+        ];
+        fill_remaining_spots(&mut grid, Some("hi, wasm")).unwrap();
+        assert_eq!(grid[1], vec!['H', 'I', MASKED_CELL, 'W']);
+        assert_eq!(grid[2], vec!['A', 'A', 'S', 'M']);
+    }
+
+    #[test]
+    fn hidden_message_too_long() {
+        let mut grid = vec![vec!['.', 'X'], vec!['X', '.']];
+        assert_eq!(
+            fill_remaining_spots(&mut grid, Some("RUST")),
+            Err(GeneratorError::HiddenMessageTooLong {
+                letters: 4,
+                free_cells: 2
+            })
+        );
+    }
 }
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+/// Predefined shapes that can be used as a mask for the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Shape {
+    Circle,
+    Diamond,
+    Heart,
+    Star,
+}
+
+/// Restricts which cells of the grid can be used for letters.
+///
+/// In JSON, a mask is either a predefined shape (`{"shape": "heart"}`)
+/// or ASCII art (`{"ascii": ["..##..", ".####.", "######"]}`). In ASCII art,
+/// `.` and spaces mark unusable cells, every other character (typically `#`)
+/// marks a usable cell. Generated grids contain a space in unusable cells.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum MaskSpec {
+    Shape(Shape),
+    Ascii(Vec<String>),
+}
+
+impl MaskSpec {
+    /// Width and height of an ASCII art mask, `None` for shapes
+    /// (they adapt to any grid size).
+    pub fn dimensions(&self) -> Option<(usize, usize)> {
+        match self {
+            MaskSpec::Shape(_) => None,
+            MaskSpec::Ascii(lines) => Some((
+                lines.iter().map(|l| l.chars().count()).max().unwrap_or(0),
+                lines.len(),
+            )),
+        }
+    }
+
+    /// Turns the specification into a mask for a grid of the given size.
+    pub fn to_mask(&self, width: usize, height: usize) -> Mask {
+        match self {
+            MaskSpec::Shape(shape) => Mask::from_shape(*shape, width, height),
+            MaskSpec::Ascii(lines) => Mask::from_ascii(lines, width, height),
+        }
+    }
+}
+
+/// Usable cells of a grid (`true` = letters may be placed in the cell).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    cells: Vec<Vec<bool>>,
+}
+
+impl Mask {
+    /// Creates a mask in which every cell is usable.
+    pub fn full(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![vec![true; width]; height],
+        }
+    }
+
+    /// Creates a mask from ASCII art. Lines shorter than `width` and
+    /// missing lines are treated as unusable.
+    pub fn from_ascii<S: AsRef<str>>(lines: &[S], width: usize, height: usize) -> Self {
+        let mut cells = vec![vec![false; width]; height];
+        for (row, line) in lines.iter().take(height).enumerate() {
+            for (col, c) in line.as_ref().chars().take(width).enumerate() {
+                cells[row][col] = c != '.' && !c.is_whitespace();
+            }
+        }
+
+        Self { cells }
+    }
+
+    /// Creates a mask by sampling the given shape at the center of every cell.
+    pub fn from_shape(shape: Shape, width: usize, height: usize) -> Self {
+        let cells = (0..height)
+            .map(|row| {
+                (0..width)
+                    .map(|col| {
+                        // Map cell center to [-1, 1], y pointing upwards
+                        let x = (col as f64 + 0.5) / width as f64 * 2f64 - 1f64;
+                        let y = 1f64 - (row as f64 + 0.5) / height as f64 * 2f64;
+                        shape_contains(shape, x, y)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { cells }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.first().map(|r| r.len()).unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_usable(&self, row: usize, col: usize) -> bool {
+        self.cells[row][col]
+    }
+
+    /// Number of usable cells
+    pub fn usable_cells(&self) -> usize {
+        self.cells.iter().flatten().filter(|c| **c).count()
+    }
+}
+
+fn shape_contains(shape: Shape, x: f64, y: f64) -> bool {
+    match shape {
+        Shape::Circle => x * x + y * y <= 1f64,
+        Shape::Diamond => x.abs() + y.abs() <= 1f64,
+        Shape::Heart => {
+            // Classic heart curve (x² + y² - 1)³ - x²y³ <= 0, scaled to fill the grid
+            let x = x * 1.2f64;
+            let y = y * 1.3f64 + 0.2f64;
+            (x * x + y * y - 1f64).powi(3) - x * x * y.powi(3) <= 0f64
+        }
+        Shape::Star => {
+            // Five-pointed star polygon with alternating outer and inner vertices
+            const POINTS: usize = 5;
+            const INNER_RADIUS: f64 = 0.5f64;
+            let vertices: Vec<(f64, f64)> = (0..POINTS * 2)
+                .map(|i| {
+                    let radius = if i % 2 == 0 { 1f64 } else { INNER_RADIUS };
+                    let angle = std::f64::consts::FRAC_PI_2
+                        + i as f64 * std::f64::consts::PI / POINTS as f64;
+                    (radius * angle.cos(), radius * angle.sin() - 0.1f64)
+                })
+                .collect();
+            polygon_contains(&vertices, x, y)
+        }
+    }
+}
+
+/// Even-odd rule point-in-polygon test
+fn polygon_contains(vertices: &[(f64, f64)], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut j = vertices.len() - 1;
+    for i in 0..vertices.len() {
+        let (xi, yi) = vertices[i];
+        let (xj, yj) = vertices[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_mask() {
+        let mask = Mask::from_ascii(&["#.#", "##"], 3, 3);
+        assert!(mask.is_usable(0, 0));
+        assert!(!mask.is_usable(0, 1));
+        assert!(!mask.is_usable(1, 2));
+        assert!(!mask.is_usable(2, 0));
+        assert_eq!(mask.usable_cells(), 4);
+    }
+
+    #[test]
+    fn shapes_leave_corners_unusable() {
+        for shape in [Shape::Circle, Shape::Diamond, Shape::Heart, Shape::Star] {
+            let mask = Mask::from_shape(shape, 15, 12);
+            assert_eq!((mask.width(), mask.height()), (15, 12));
+            assert!(!mask.is_usable(11, 0), "{shape:?}");
+            assert!(!mask.is_usable(11, 14), "{shape:?}");
+            assert!(mask.usable_cells() > 15 * 12 / 4, "{shape:?}");
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Printable output (SVG and PDF) for generated puzzles.
+//!
+//! Both formats share the same page layout (A4 portrait, coordinates in points
+//! with the origin in the upper left corner) calculated by [`PageLayout`].
+
+use crate::{Puzzle, MASKED_CELL};
+
+mod pdf;
+mod svg;
+
+pub use pdf::*;
+pub use svg::*;
+
+/// Options for printable output
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Title printed at the top of the page
+    pub title: String,
+    /// Adds a separate page with the placed words circled
+    pub answer_key: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            title: "Word Puzzle".to_string(),
+            answer_key: false,
+        }
+    }
+}
+
+const PAGE_WIDTH: f64 = 595f64;
+const PAGE_HEIGHT: f64 = 842f64;
+const MARGIN: f64 = 50f64;
+const TITLE_BASELINE: f64 = 90f64;
+const TITLE_FONT_SIZE: f64 = 24f64;
+const GRID_TOP: f64 = 120f64;
+const MAX_GRID_HEIGHT: f64 = 480f64;
+/// The grid shrinks down to this height to make room for long word lists
+const MIN_GRID_HEIGHT: f64 = 240f64;
+const MAX_CELL_SIZE: f64 = 30f64;
+const WORD_LIST_COLUMNS: usize = 3;
+const WORD_FONT_SIZE: f64 = 12f64;
+const WORD_LINE_HEIGHT: f64 = 18f64;
+const WORD_LIST_GAP: f64 = 40f64;
+const ANSWER_KEY_SUFFIX: &str = " (Answer Key)";
+
+/// Letter with the position of its center
+struct PlacedLetter {
+    letter: char,
+    x: f64,
+    y: f64,
+}
+
+/// Word of the word list with the position of its baseline start
+struct PlacedText<'a> {
+    text: &'a str,
+    x: f64,
+    y: f64,
+}
+
+/// Outline around a placed word (answer key), centered at `x`/`y` and
+/// rotated by `angle` degrees (clockwise, because y points downwards).
+struct WordOutline {
+    x: f64,
+    y: f64,
+    length: f64,
+    thickness: f64,
+    angle: f64,
+}
+
+/// Positions of all elements on a page
+struct PageLayout<'a> {
+    cell_size: f64,
+    /// Font size of the word list, smaller than [`WORD_FONT_SIZE`] if the
+    /// list would not fit on the page otherwise
+    word_font_size: f64,
+    letters: Vec<PlacedLetter>,
+    words: Vec<PlacedText<'a>>,
+    outlines: Vec<WordOutline>,
+}
+
+impl<'a> PageLayout<'a> {
+    fn new(puzzle: &'a Puzzle, with_answers: bool) -> Self {
+        let rows = puzzle.grid.len().max(1);
+        let cols = puzzle
+            .grid
+            .iter()
+            .map(|r| r.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        // Shrink the grid if the word list would not fit below it
+        let per_column = puzzle.words.len().div_ceil(WORD_LIST_COLUMNS).max(1);
+        let list_height = per_column as f64 * WORD_LINE_HEIGHT;
+        let grid_height = (PAGE_HEIGHT - MARGIN - GRID_TOP - WORD_LIST_GAP - list_height)
+            .clamp(MIN_GRID_HEIGHT, MAX_GRID_HEIGHT);
+        let cell_size = ((PAGE_WIDTH - 2f64 * MARGIN) / cols as f64)
+            .min(grid_height / rows as f64)
+            .min(MAX_CELL_SIZE);
+        let grid_left = (PAGE_WIDTH - cell_size * cols as f64) / 2f64;
+        let cell_center = |row: usize, col: usize| {
+            (
+                grid_left + (col as f64 + 0.5f64) * cell_size,
+                GRID_TOP + (row as f64 + 0.5f64) * cell_size,
+            )
+        };
+
+        let letters = puzzle
+            .grid
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|(_, letter)| *letter != MASKED_CELL)
+                    .map(move |(col, letter)| (row, col, letter))
+            })
+            .map(|(row, col, letter)| {
+                let (x, y) = cell_center(row, col);
+                PlacedLetter { letter, x, y }
+            })
+            .collect();
+
+        // Word list in columns below the grid, with smaller text if even the
+        // smallest grid leaves too little room
+        let list_top = GRID_TOP + rows as f64 * cell_size + WORD_LIST_GAP;
+        let scale = ((PAGE_HEIGHT - MARGIN - list_top) / list_height).min(1f64);
+        let line_height = WORD_LINE_HEIGHT * scale;
+        let column_width = (PAGE_WIDTH - 2f64 * MARGIN) / WORD_LIST_COLUMNS as f64;
+        let words = puzzle
+            .words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| PlacedText {
+                text: word,
+                x: MARGIN + (i / per_column) as f64 * column_width,
+                y: list_top + (i % per_column) as f64 * line_height,
+            })
+            .collect();
+
+        let outlines = if with_answers {
+            puzzle
+                .placements
+                .iter()
+                .map(|p| {
+                    let (start_x, start_y) = cell_center(p.row, p.col);
+                    let (end_row, end_col) = p.end();
+                    let (end_x, end_y) = cell_center(end_row, end_col);
+                    let (dx, dy) = (end_x - start_x, end_y - start_y);
+                    WordOutline {
+                        x: (start_x + end_x) / 2f64,
+                        y: (start_y + end_y) / 2f64,
+                        length: (dx * dx + dy * dy).sqrt() + cell_size * 0.8f64,
+                        thickness: cell_size * 0.8f64,
+                        angle: dy.atan2(dx).to_degrees(),
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            cell_size,
+            word_font_size: WORD_FONT_SIZE * scale,
+            letters,
+            words,
+            outlines,
+        }
+    }
+
+    fn letter_font_size(&self) -> f64 {
+        self.cell_size * 0.6f64
+    }
+}
+
+/// Title for the regular page or the answer key page
+fn page_title(options: &RenderOptions, answer_key: bool) -> String {
+    if answer_key {
+        format!("{}{ANSWER_KEY_SUFFIX}", options.title)
+    } else {
+        options.title.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, WordPlacement};
+
+    pub(super) fn puzzle() -> Puzzle {
+        Puzzle {
+            grid: vec!["RUST".to_string(), format!("X{MASKED_CELL}AB")],
+            words: vec!["RUST".to_string()],
+            placements: vec![WordPlacement {
+                word: "RUST".to_string(),
+                row: 0,
+                col: 0,
+                direction: Direction::Horizontal,
+                reversed: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let puzzle = puzzle();
+        let layout = PageLayout::new(&puzzle, true);
+        assert_eq!(layout.cell_size, MAX_CELL_SIZE);
+        assert_eq!(layout.letters.len(), 7);
+        assert_eq!(layout.words.len(), 1);
+        assert_eq!(layout.outlines.len(), 1);
+        assert_eq!(layout.outlines[0].angle, 0f64);
+        assert_eq!(layout.outlines[0].x, PAGE_WIDTH / 2f64);
+
+        assert!(PageLayout::new(&puzzle, false).outlines.is_empty());
+    }
+
+    #[test]
+    fn long_word_lists_stay_on_the_page() {
+        for count in [3, 30, 90, 300] {
+            let puzzle = Puzzle {
+                grid: vec!["X".repeat(20); 20],
+                words: vec!["WORD".to_string(); count],
+                placements: Vec::new(),
+            };
+            let layout = PageLayout::new(&puzzle, false);
+            let grid_bottom = GRID_TOP + 20f64 * layout.cell_size;
+            assert!(layout.words.iter().all(|w| w.y > grid_bottom), "{count}");
+            assert!(
+                layout.words.iter().all(|w| w.y <= PAGE_HEIGHT - MARGIN),
+                "{count}"
+            );
+            assert_eq!(
+                layout.word_font_size == WORD_FONT_SIZE,
+                count <= 30,
+                "{count}"
+            );
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+
+use super::*;
+
+// Minimal PDF writer: only the standard Helvetica fonts are used, so no fonts
+// have to be embedded and the output stays small enough for edge runtimes.
+
+/// Quarter circle approximation factor for Bézier curves
+const KAPPA: f64 = 0.5523f64;
+
+/// Renders the puzzle as a PDF document. If [`RenderOptions::answer_key`] is set,
+/// a second page with the placed words circled is added.
+pub fn render_pdf(puzzle: &Puzzle, options: &RenderOptions) -> Vec<u8> {
+    let mut pages = vec![pdf_page(puzzle, &page_title(options, false), false)];
+    if options.answer_key {
+        pages.push(pdf_page(puzzle, &page_title(options, true), true));
+    }
+
+    write_document(&pages)
+}
+
+/// Creates the content stream of a single page
+fn pdf_page(puzzle: &Puzzle, title: &str, with_answers: bool) -> Vec<u8> {
+    let layout = PageLayout::new(puzzle, with_answers);
+    let mut content = Vec::new();
+
+    let title_width = text_width(title, TITLE_FONT_SIZE);
+    write_text(
+        &mut content,
+        "F2",
+        TITLE_FONT_SIZE,
+        (PAGE_WIDTH - title_width) / 2f64,
+        PAGE_HEIGHT - TITLE_BASELINE,
+        title,
+    );
+
+    // Outlines are drawn in a rotated coordinate system centered on the word.
+    // PDF's y axis points upwards, so the rotation has to be inverted.
+    for outline in layout.outlines.iter() {
+        let (sin, cos) = (-outline.angle).to_radians().sin_cos();
+        let a = outline.length / 2f64;
+        let r = outline.thickness / 2f64;
+        let k = KAPPA * r;
+        let mut path = String::new();
+        let _ = write!(
+            path,
+            "q 1 0 0 RG 1.5 w {cos:.4} {sin:.4} {:.4} {cos:.4} {:.2} {:.2} cm ",
+            -sin,
+            outline.x,
+            PAGE_HEIGHT - outline.y
+        );
+        let _ = write!(path, "{:.2} {:.2} m {:.2} {:.2} l ", r - a, -r, a - r, -r);
+        let _ = write!(
+            path,
+            "{:.2} {:.2} {a:.2} {:.2} {a:.2} 0 c {a:.2} {k:.2} {:.2} {r:.2} {:.2} {r:.2} c ",
+            a - r + k,
+            -r,
+            -k,
+            a - r + k,
+            a - r
+        );
+        let _ = write!(path, "{:.2} {r:.2} l ", r - a);
+        let _ = writeln!(
+            path,
+            "{:.2} {r:.2} {:.2} {k:.2} {:.2} 0 c {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c h S Q",
+            r - a - k,
+            -a,
+            -a,
+            -a,
+            -k,
+            r - a - k,
+            -r,
+            r - a,
+            -r
+        );
+        content.extend_from_slice(path.as_bytes());
+    }
+
+    let letter_size = layout.letter_font_size();
+    for letter in layout.letters.iter() {
+        let text = letter.letter.to_string();
+        write_text(
+            &mut content,
+            "F1",
+            letter_size,
+            letter.x - text_width(&text, letter_size) / 2f64,
+            // Cap height of Helvetica is ~0.72 em, move baseline to center capitals
+            PAGE_HEIGHT - letter.y - 0.36f64 * letter_size,
+            &text,
+        );
+    }
+
+    for word in layout.words.iter() {
+        write_text(
+            &mut content,
+            "F1",
+            layout.word_font_size,
+            word.x,
+            PAGE_HEIGHT - word.y,
+            word.text,
+        );
+    }
+
+    content
+}
+
+fn write_text(content: &mut Vec<u8>, font: &str, size: f64, x: f64, y: f64, text: &str) {
+    content.extend_from_slice(format!("BT /{font} {size:.2} Tf {x:.2} {y:.2} Td (").as_bytes());
+    for c in text.chars() {
+        // Standard fonts use WinAnsiEncoding, which matches Latin-1 for letters
+        let byte = if (c as u32) < 256 { c as u8 } else { b'?' };
+        if matches!(byte, b'(' | b')' | b'\\') {
+            content.push(b'\\');
+        }
+        content.push(byte);
+    }
+    content.extend_from_slice(b") Tj ET\n");
+}
+
+/// Approximate width of text set in Helvetica
+fn text_width(text: &str, size: f64) -> f64 {
+    // Glyph widths of Helvetica capitals A-Z (1/1000 em)
+    const CAPITAL_WIDTHS: [u16; 26] = [
+        667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722,
+        667, 611, 722, 667, 944, 667, 667, 611,
+    ];
+    const DEFAULT_WIDTH: u16 = 556;
+
+    text.chars()
+        .map(|c| match c {
+            'A'..='Z' => CAPITAL_WIDTHS[(c as u8 - b'A') as usize],
+            ' ' => 278,
+            _ => DEFAULT_WIDTH,
+        })
+        .map(|w| w as f64 * size / 1000f64)
+        .sum()
+}
+
+/// Writes catalog, page tree, fonts, pages, and cross-reference table.
+fn write_document(pages: &[Vec<u8>]) -> Vec<u8> {
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 5 + 2 * i).collect();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (page_id, content) in page_ids.iter().zip(pages) {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                page_id + 1
+            )
+            .into_bytes(),
+        );
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(xref, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        xref,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(xref.as_bytes());
+
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::puzzle;
+
+    #[test]
+    fn pdf_structure() {
+        let pdf = render_pdf(&puzzle(), &RenderOptions::default());
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 1"));
+        assert!(text.contains("(Word Puzzle) Tj"));
+
+        // Cross-reference table must point to the objects
+        let xref_offset: usize = text
+            .split("startxref\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(text[xref_offset..].starts_with("xref"));
+        let first_object: usize = text[xref_offset..].lines().nth(3).unwrap()[..10]
+            .parse()
+            .unwrap();
+        assert!(text[first_object..].starts_with("1 0 obj"));
+    }
+
+    #[test]
+    fn answer_key_page() {
+        let options = RenderOptions {
+            answer_key: true,
+            ..Default::default()
+        };
+        let pdf = render_pdf(&puzzle(), &options);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("(Word Puzzle \\(Answer Key\\)) Tj"));
+        assert_eq!(text.matches("h S Q").count(), 1);
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use super::*;
+
+/// Renders the puzzle page as SVG.
+pub fn render_svg(puzzle: &Puzzle, options: &RenderOptions) -> String {
+    svg_page(puzzle, &page_title(options, false), false)
+}
+
+/// Renders the answer key page (placed words circled) as SVG.
+pub fn render_answer_key_svg(puzzle: &Puzzle, options: &RenderOptions) -> String {
+    svg_page(puzzle, &page_title(options, true), true)
+}
+
+fn svg_page(puzzle: &Puzzle, title: &str, with_answers: bool) -> String {
+    let layout = PageLayout::new(puzzle, with_answers);
+
+    // Writing into a String cannot fail, so results of `write!` are ignored
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{PAGE_WIDTH}pt" height="{PAGE_HEIGHT}pt" viewBox="0 0 {PAGE_WIDTH} {PAGE_HEIGHT}" font-family="Helvetica, Arial, sans-serif">"#
+    );
+    let _ = write!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="white"/><text x="{:.2}" y="{TITLE_BASELINE}" font-size="{TITLE_FONT_SIZE}" font-weight="bold" text-anchor="middle">{}</text>"#,
+        PAGE_WIDTH / 2f64,
+        escape(title)
+    );
+
+    for outline in layout.outlines.iter() {
+        let _ = write!(
+            svg,
+            r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" rx="{:.2}" fill="none" stroke="red" stroke-width="1.5" transform="rotate({:.2} {:.2} {:.2})"/>"#,
+            outline.x - outline.length / 2f64,
+            outline.y - outline.thickness / 2f64,
+            outline.length,
+            outline.thickness,
+            outline.thickness / 2f64,
+            outline.angle,
+            outline.x,
+            outline.y
+        );
+    }
+
+    let _ = write!(
+        svg,
+        r#"<g font-size="{:.2}" text-anchor="middle" dominant-baseline="central">"#,
+        layout.letter_font_size()
+    );
+    for letter in layout.letters.iter() {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}">{}</text>"#,
+            letter.x,
+            letter.y,
+            escape(&letter.letter.to_string())
+        );
+    }
+    svg.push_str("</g>");
+
+    let _ = write!(svg, r#"<g font-size="{}">"#, layout.word_font_size);
+    for word in layout.words.iter() {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}">{}</text>"#,
+            word.x,
+            word.y,
+            escape(word.text)
+        );
+    }
+    svg.push_str("</g></svg>");
+
+    svg
+}
+
+/// Escapes characters with special meaning in XML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::puzzle;
+
+    #[test]
+    fn puzzle_and_answer_key() {
+        let options = RenderOptions {
+            title: "Rust & WASM".to_string(),
+            ..Default::default()
+        };
+
+        let svg = render_svg(&puzzle(), &options);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains(">Rust &amp; WASM</text>"));
+        assert!(!svg.contains("stroke=\"red\""));
+        assert_eq!(svg.matches("<text").count(), 1 + 7 + 1);
+
+        let answer_key = render_answer_key_svg(&puzzle(), &options);
+        assert!(answer_key.contains("(Answer Key)"));
+        assert_eq!(answer_key.matches("stroke=\"red\"").count(), 1);
+    }
+}
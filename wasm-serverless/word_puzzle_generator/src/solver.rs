@@ -0,0 +1,315 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+
+/// Row and column steps of the eight directions in which words can be written
+const STEPS: [(isize, isize); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// Occurrence of a word in the grid
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Occurrence {
+    /// Row of the first letter
+    pub row: usize,
+    /// Column of the first letter
+    pub col: usize,
+    /// Row step from one letter to the next (-1, 0, or 1)
+    pub row_step: isize,
+    /// Column step from one letter to the next (-1, 0, or 1)
+    pub col_step: isize,
+}
+
+impl Occurrence {
+    /// Row and column of every letter of an occurrence of a word with `len` letters
+    pub fn cells(&self, len: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..len as isize).map(move |i| {
+            (
+                (self.row as isize + i * self.row_step) as usize,
+                (self.col as isize + i * self.col_step) as usize,
+            )
+        })
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SolutionStatus {
+    /// Word occurs exactly once (see [`solve`] for words that are part of
+    /// longer words)
+    Unique,
+    /// Word occurs more than once
+    Ambiguous,
+    /// Word does not occur at all
+    Missing,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct WordSolution {
+    pub word: String,
+    pub status: SolutionStatus,
+    /// All occurrences, those that are not part of a longer word first
+    pub occurrences: Vec<Occurrence>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Solution {
+    pub words: Vec<WordSolution>,
+}
+
+impl Solution {
+    /// `true` if every word occurs exactly once
+    pub fn is_unique(&self) -> bool {
+        self.words
+            .iter()
+            .all(|w| w.status == SolutionStatus::Unique)
+    }
+}
+
+/// Cells covered by an occurrence
+type Cells = BTreeSet<(usize, usize)>;
+
+/// Node of a trie containing all words to search for
+#[derive(Default)]
+struct TrieNode {
+    children: HashMap<char, TrieNode>,
+    /// Indexes of the words ending in this node
+    words: Vec<usize>,
+}
+
+impl TrieNode {
+    fn insert(&mut self, word: &str, index: usize) {
+        let node = word
+            .chars()
+            .flat_map(char::to_uppercase)
+            .fold(self, |node, c| node.children.entry(c).or_default());
+        node.words.push(index);
+    }
+}
+
+/// Finds every occurrence of each word in all eight directions.
+///
+/// The grid can contain cells that are not letters (e.g. [`crate::MASKED_CELL`]),
+/// they never match. Comparison is case-insensitive. A palindrome found forwards
+/// and backwards on the same cells counts as one occurrence.
+///
+/// Occurrences that lie within an occurrence of a longer word of the list
+/// (`SUN` in `SUNDAY`) are part of that word. They only count if the word is
+/// not found on its own, so `SUN` is unique if it occurs once outside of
+/// `SUNDAY` or once within it.
+pub fn solve(grid: &[String], words: &[String]) -> Solution {
+    let grid: Vec<Vec<char>> = grid
+        .iter()
+        .map(|r| r.chars().flat_map(char::to_uppercase).collect())
+        .collect();
+
+    let mut trie = TrieNode::default();
+    for (index, word) in words.iter().enumerate() {
+        trie.insert(word, index);
+    }
+
+    // Walk the trie from every cell in every direction
+    let mut occurrences: Vec<Vec<(Occurrence, Cells)>> = vec![Vec::new(); words.len()];
+    let mut seen_cells: Vec<BTreeSet<Cells>> = vec![BTreeSet::new(); words.len()];
+    for (row, line) in grid.iter().enumerate() {
+        for col in 0..line.len() {
+            for (row_step, col_step) in STEPS {
+                let mut node = &trie;
+                let mut cells = BTreeSet::new();
+                let (mut r, mut c) = (row as isize, col as isize);
+                while let Some(letter) = cell(&grid, r, c) {
+                    let Some(next) = node.children.get(&letter) else {
+                        break;
+                    };
+                    node = next;
+                    cells.insert((r as usize, c as usize));
+
+                    for index in node.words.iter() {
+                        if seen_cells[*index].insert(cells.clone()) {
+                            let occurrence = Occurrence {
+                                row,
+                                col,
+                                row_step,
+                                col_step,
+                            };
+                            occurrences[*index].push((occurrence, cells.clone()));
+                        }
+                    }
+
+                    r += row_step;
+                    c += col_step;
+                }
+            }
+        }
+    }
+
+    let is_nested = |index: usize, cells: &Cells| {
+        seen_cells.iter().enumerate().any(|(other, sets)| {
+            other != index
+                && sets
+                    .iter()
+                    .any(|set| set.len() > cells.len() && set.is_superset(cells))
+        })
+    };
+
+    Solution {
+        words: words
+            .iter()
+            .zip(occurrences)
+            .enumerate()
+            .map(|(index, (word, occurrences))| {
+                let (nested, standalone): (Vec<_>, Vec<_>) = occurrences
+                    .into_iter()
+                    .partition(|(_, cells)| is_nested(index, cells));
+                let status = match (standalone.len(), nested.len()) {
+                    (0, 0) => SolutionStatus::Missing,
+                    (1, _) | (0, 1) => SolutionStatus::Unique,
+                    _ => SolutionStatus::Ambiguous,
+                };
+
+                WordSolution {
+                    word: word.clone(),
+                    status,
+                    occurrences: standalone
+                        .into_iter()
+                        .chain(nested)
+                        .map(|(occurrence, _)| occurrence)
+                        .collect(),
+                }
+            })
+            .collect(),
+    }
+}
+
+fn cell(grid: &[Vec<char>], row: isize, col: isize) -> Option<char> {
+    if row < 0 || col < 0 {
+        return None;
+    }
+
+    grid.get(row as usize)
+        .and_then(|r| r.get(col as usize))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_words_in_all_directions() {
+        let grid = strings(&["RUSTX", "XSAWX", "TACOX", "XMXXX"]);
+        let solution = solve(
+            &grid,
+            &strings(&["rust", "WAS", "SAW", "TACO", "OCA", "MAS"]),
+        );
+
+        let occurrence = |word: usize| solution.words[word].occurrences[0];
+        assert_eq!(
+            occurrence(0),
+            Occurrence {
+                row: 0,
+                col: 0,
+                row_step: 0,
+                col_step: 1
+            }
+        );
+        assert_eq!(
+            occurrence(1),
+            Occurrence {
+                row: 1,
+                col: 3,
+                row_step: 0,
+                col_step: -1
+            }
+        );
+        assert_eq!(
+            occurrence(3),
+            Occurrence {
+                row: 2,
+                col: 0,
+                row_step: 0,
+                col_step: 1
+            }
+        );
+        assert_eq!(
+            occurrence(4),
+            Occurrence {
+                row: 2,
+                col: 3,
+                row_step: 0,
+                col_step: -1
+            }
+        );
+        assert_eq!(
+            occurrence(5),
+            Occurrence {
+                row: 3,
+                col: 1,
+                row_step: -1,
+                col_step: 0
+            }
+        );
+        assert!(solution.is_unique());
+    }
+
+    #[test]
+    fn flags_missing_and_ambiguous_words() {
+        let grid = strings(&["ABAB", "XXXX"]);
+        let solution = solve(&grid, &strings(&["AB", "CLOUD"]));
+        assert_eq!(solution.words[0].status, SolutionStatus::Ambiguous);
+        // Twice forwards, once backwards starting at the second A
+        assert_eq!(solution.words[0].occurrences.len(), 3);
+        assert_eq!(solution.words[1].status, SolutionStatus::Missing);
+        assert!(!solution.is_unique());
+
+        let solution = solve(&grid, &strings(&["AB", "ABA"]));
+        // Palindrome found forwards and backwards on the same cells
+        assert_eq!(solution.words[1].status, SolutionStatus::Unique);
+        // Only the last AB is not part of ABA
+        assert_eq!(solution.words[0].status, SolutionStatus::Unique);
+        assert_eq!(
+            (
+                solution.words[0].occurrences[0].col,
+                solution.words[0].occurrences[0].col_step
+            ),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    fn words_within_longer_words_are_part_of_them() {
+        // SUN on its own and within SUNDAY
+        let solution = solve(
+            &strings(&["SUNDAY", "XXXSUN"]),
+            &strings(&["SUN", "SUNDAY"]),
+        );
+        assert!(solution.is_unique());
+        assert_eq!(solution.words[0].occurrences.len(), 2);
+        assert_eq!(solution.words[0].occurrences[0].row, 1);
+
+        // SUN only within SUNDAY
+        let solution = solve(&strings(&["SUNDAY"]), &strings(&["SUN", "SUNDAY"]));
+        assert!(solution.is_unique());
+
+        // SUN twice on its own or within two longer words
+        let solution = solve(&strings(&["SUNSUN"]), &strings(&["SUN"]));
+        assert_eq!(solution.words[0].status, SolutionStatus::Ambiguous);
+        let solution = solve(
+            &strings(&["SUNDAY", "SUNNYX"]),
+            &strings(&["SUN", "SUNDAY", "SUNNY"]),
+        );
+        assert_eq!(solution.words[0].status, SolutionStatus::Ambiguous);
+    }
+}
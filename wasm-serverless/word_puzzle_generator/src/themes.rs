@@ -0,0 +1,229 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::Serialize;
+
+/// Version of the bundled word lists. Increase it whenever a list changes
+/// so that clients can detect that the same theme yields different words.
+pub const THEME_LIBRARY_VERSION: &str = "1.0.0";
+
+/// Language used if a theme is requested without language
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Number of words picked from a theme if no word count is given
+pub const DEFAULT_THEME_WORD_COUNT: usize = 10;
+
+/// Maximum number of words that can be picked from a theme
+pub const MAX_THEME_WORD_COUNT: usize = 30;
+
+/// Shortest words picked from a theme if no minimum length is given
+pub const DEFAULT_MIN_WORD_LENGTH: usize = 3;
+
+/// Share of grid cells that words picked from a theme may use at most
+const MAX_LETTER_DENSITY: f64 = 0.6f64;
+
+/// Bundled word list
+pub struct Theme {
+    pub id: &'static str,
+    pub language: &'static str,
+    source: &'static str,
+}
+
+impl Theme {
+    /// Words of the theme; lines starting with `#` are comments
+    pub fn words(&self) -> impl Iterator<Item = &'static str> {
+        self.source
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+    }
+}
+
+const THEMES: &[Theme] = &[
+    Theme {
+        id: "animals",
+        language: "en",
+        source: include_str!("../themes/en/animals.txt"),
+    },
+    Theme {
+        id: "countries",
+        language: "en",
+        source: include_str!("../themes/en/countries.txt"),
+    },
+    Theme {
+        id: "programming",
+        language: "en",
+        source: include_str!("../themes/en/programming.txt"),
+    },
+    Theme {
+        id: "animals",
+        language: "de",
+        source: include_str!("../themes/de/animals.txt"),
+    },
+    Theme {
+        id: "countries",
+        language: "de",
+        source: include_str!("../themes/de/countries.txt"),
+    },
+    Theme {
+        id: "programming",
+        language: "de",
+        source: include_str!("../themes/de/programming.txt"),
+    },
+];
+
+/// Looks up a bundled theme
+pub fn find_theme(id: &str, language: &str) -> Option<&'static Theme> {
+    THEMES
+        .iter()
+        .find(|t| t.id.eq_ignore_ascii_case(id) && t.language.eq_ignore_ascii_case(language))
+}
+
+/// Entry in the list of available themes
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeInfo {
+    pub id: &'static str,
+    pub language: &'static str,
+    pub word_count: usize,
+}
+
+/// All bundled themes together with the library version
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct ThemeLibrary {
+    pub version: &'static str,
+    pub themes: Vec<ThemeInfo>,
+}
+
+pub fn available_themes() -> ThemeLibrary {
+    ThemeLibrary {
+        version: THEME_LIBRARY_VERSION,
+        themes: THEMES
+            .iter()
+            .map(|t| ThemeInfo {
+                id: t.id,
+                language: t.language,
+                word_count: t.words().count(),
+            })
+            .collect(),
+    }
+}
+
+/// Criteria for picking words from a theme
+pub struct ThemeSelection<'a> {
+    pub count: usize,
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Number of usable cells in the grid
+    pub cells: usize,
+    /// Words containing one of these words are skipped
+    pub blocklist: &'a [String],
+}
+
+/// Randomly picks words from the theme that match the selection criteria.
+///
+/// Stops early if the picked words would fill the grid too much
+/// to place them reliably.
+pub fn select_words(theme: &Theme, selection: &ThemeSelection, rng: &mut impl Rng) -> Vec<String> {
+    let mut candidates: Vec<&str> = theme
+        .words()
+        .filter(|w| (selection.min_length..=selection.max_length).contains(&w.chars().count()))
+        .filter(|w| {
+            let reversed: String = w.chars().rev().collect();
+            !selection
+                .blocklist
+                .iter()
+                .filter(|b| !b.is_empty())
+                .any(|b| {
+                    let blocked = b.to_uppercase();
+                    w.contains(&blocked) || reversed.contains(&blocked)
+                })
+        })
+        .collect();
+    candidates.shuffle(rng);
+
+    let max_letters = (selection.cells as f64 * MAX_LETTER_DENSITY) as usize;
+    let mut letters = 0;
+    let mut words = Vec::new();
+    for word in candidates {
+        if words.len() == selection.count {
+            break;
+        }
+
+        // A word contained in another one would occur more than once
+        let reversed: String = word.chars().rev().collect();
+        let overlaps = words.iter().any(|w: &String| {
+            w.contains(word)
+                || w.contains(&reversed)
+                || word.contains(w.as_str())
+                || reversed.contains(w.as_str())
+        });
+
+        let len = word.chars().count();
+        if !overlaps && letters + len <= max_letters {
+            letters += len;
+            words.push(word.to_string());
+        }
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_themes_are_valid() {
+        for theme in THEMES {
+            let words: Vec<&str> = theme.words().collect();
+            assert!(words.len() >= 30, "{} ({})", theme.id, theme.language);
+            assert!(words
+                .iter()
+                .all(|w| w.chars().all(|c| c.is_ascii_uppercase())));
+        }
+
+        assert!(find_theme("Animals", "DE").is_some());
+        assert!(find_theme("animals", "xx").is_none());
+    }
+
+    #[test]
+    fn select_words_by_length_and_grid_size() {
+        let theme = find_theme("animals", "en").unwrap();
+        let selection = ThemeSelection {
+            count: 5,
+            min_length: 4,
+            max_length: 6,
+            cells: 100,
+            blocklist: &["ON".to_string()],
+        };
+        let words = select_words(theme, &selection, &mut rand::thread_rng());
+        assert_eq!(words.len(), 5);
+        assert!(words.iter().all(|w| (4..=6).contains(&w.len())));
+        assert!(words.iter().all(|w| !w.contains("ON") && !w.contains("NO")));
+
+        // No word is part of another word
+        let all = ThemeSelection {
+            count: 30,
+            min_length: 3,
+            max_length: 20,
+            cells: 1000,
+            blocklist: &[],
+        };
+        for _ in 0..20 {
+            let words = select_words(theme, &all, &mut rand::thread_rng());
+            for (a, b) in words.iter().flat_map(|a| words.iter().map(move |b| (a, b))) {
+                let reversed: String = b.chars().rev().collect();
+                assert!(a == b || !(a.contains(b.as_str()) || a.contains(&reversed)));
+            }
+        }
+
+        // Only few letters fit into a small grid
+        let small = ThemeSelection {
+            cells: 10,
+            ..selection
+        };
+        let words = select_words(theme, &small, &mut rand::thread_rng());
+        assert!(words.iter().map(|w| w.len()).sum::<usize>() <= 6);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+
+use crate::{random_letter, solve, GeneratorError, Occurrence, SolutionStatus, WordPlacement};
+
+/// Maximum number of times filler cells are re-rolled to get a unique solution
+const MAX_REROLLS: usize = 100;
+
+/// Makes sure that every placed word occurs exactly once and no blocked word
+/// occurs at all by re-rolling filler cells.
+///
+/// Occurrences within a longer placed word do not count (see [`solve`]), so
+/// word lists like `SUN` and `SUNDAY` work. The occurrence that counts must be
+/// the placement, otherwise the answer key would be wrong.
+///
+/// Cells of placed words and `fixed_cells` (e.g. hidden message letters) are
+/// never changed. If an unwanted occurrence consists of such cells only, the
+/// grid cannot be fixed and an error is returned.
+pub(crate) fn ensure_unique(
+    grid: &mut [Vec<char>],
+    placements: &[WordPlacement],
+    blocklist: &[String],
+    fixed_cells: &[(usize, usize)],
+    rng: &mut impl Rng,
+) -> Result<(), GeneratorError> {
+    let fixed: HashSet<(usize, usize)> = placements
+        .iter()
+        .flat_map(|p| p.cells())
+        .chain(fixed_cells.iter().copied())
+        .collect();
+
+    let words: Vec<String> = placements.iter().map(|p| p.word.clone()).collect();
+    let rerollable = |occurrence: &Occurrence, word: &str| -> Vec<(usize, usize)> {
+        occurrence
+            .cells(word.chars().count())
+            .filter(|cell| !fixed.contains(cell))
+            .collect()
+    };
+
+    for _ in 0..MAX_REROLLS {
+        let rows: Vec<String> = grid.iter().map(|r| r.iter().collect()).collect();
+        let mut unwanted = Vec::new();
+
+        // Every occurrence of a placed word that would compete with its
+        // placement is unwanted. The placement itself only consists of fixed cells.
+        for (placement, word) in placements.iter().zip(solve(&rows, &words).words) {
+            let placed: HashSet<(usize, usize)> = placement.cells().collect();
+            let is_placement = |occurrence: &Occurrence| {
+                occurrence
+                    .cells(placed.len())
+                    .all(|cell| placed.contains(&cell))
+            };
+            if word.status == SolutionStatus::Unique && is_placement(&word.occurrences[0]) {
+                continue;
+            }
+
+            let others: Vec<Vec<(usize, usize)>> = word
+                .occurrences
+                .iter()
+                .filter(|occurrence| !is_placement(occurrence))
+                .map(|occurrence| rerollable(occurrence, &word.word))
+                .filter(|cells| !cells.is_empty())
+                .collect();
+            if others.is_empty() {
+                return Err(GeneratorError::AmbiguousWord {
+                    word: word.word.clone(),
+                });
+            }
+            unwanted.extend(others);
+        }
+
+        // Every occurrence of a blocked word is unwanted
+        for word in solve(&rows, blocklist).words {
+            for occurrence in word.occurrences.iter() {
+                let cells = rerollable(occurrence, &word.word);
+                if cells.is_empty() {
+                    return Err(GeneratorError::BlockedWordInGrid {
+                        word: word.word.clone(),
+                    });
+                }
+                unwanted.push(cells);
+            }
+        }
+
+        if unwanted.is_empty() {
+            return Ok(());
+        }
+
+        for (row, col) in unwanted.into_iter().flatten() {
+            grid[row][col] = random_letter(rng);
+        }
+    }
+
+    Err(GeneratorError::UniqueSolutionNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    fn grid(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    fn rust_placement() -> WordPlacement {
+        WordPlacement {
+            word: "RUST".to_string(),
+            row: 0,
+            col: 0,
+            direction: Direction::Horizontal,
+            reversed: false,
+        }
+    }
+
+    #[test]
+    fn rerolls_duplicates_and_blocked_words() {
+        let mut rng = rand::thread_rng();
+        let mut g = grid(&["RUST", "RUST", "BADX"]);
+        ensure_unique(
+            &mut g,
+            &[rust_placement()],
+            &["BAD".to_string()],
+            &[],
+            &mut rng,
+        )
+        .unwrap();
+
+        let rows: Vec<String> = g.iter().map(|r| r.iter().collect()).collect();
+        assert_eq!(rows[0], "RUST");
+        let solution = solve(&rows, &["RUST".to_string(), "BAD".to_string()]);
+        assert_eq!(solution.words[0].occurrences.len(), 1);
+        assert!(solution.words[1].occurrences.is_empty());
+    }
+
+    #[test]
+    fn fixed_cells_cannot_be_changed() {
+        let mut rng = rand::thread_rng();
+        let mut g = grid(&["RUST", "XXXX"]);
+        assert_eq!(
+            ensure_unique(
+                &mut g,
+                &[rust_placement()],
+                &["US".to_string()],
+                &[],
+                &mut rng
+            ),
+            Err(GeneratorError::BlockedWordInGrid {
+                word: "US".to_string()
+            })
+        );
+
+        let mut g = grid(&["RUST", "RUST"]);
+        assert_eq!(
+            ensure_unique(
+                &mut g,
+                &[rust_placement()],
+                &[],
+                &[(1, 0), (1, 1), (1, 2), (1, 3)],
+                &mut rng
+            ),
+            Err(GeneratorError::AmbiguousWord {
+                word: "RUST".to_string()
+            })
+        );
+    }
+}
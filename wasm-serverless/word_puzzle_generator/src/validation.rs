@@ -0,0 +1,501 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    find_theme, GeneratorError, GeneratorOptions, DEFAULT_MIN_WORD_LENGTH, MAX_THEME_WORD_COUNT,
+};
+
+/// Smallest supported grid size
+pub const MIN_SIZE: usize = 2;
+
+/// Largest supported grid size
+pub const MAX_SIZE: usize = 20;
+
+/// Problems that can be found in [`GeneratorOptions`] before generating a puzzle.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+#[serde(
+    tag = "code",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ValidationError {
+    #[error("{dimension} {size} is out of range, must be between {min} and {max}")]
+    SizeOutOfRange {
+        dimension: &'static str,
+        size: usize,
+        min: usize,
+        max: usize,
+    },
+    #[error("mask is {mask_width}x{mask_height}, but the grid is {width}x{height}")]
+    MaskSizeMismatch {
+        mask_width: usize,
+        mask_height: usize,
+        width: usize,
+        height: usize,
+    },
+    #[error("mask does not contain any usable cells")]
+    EmptyMask,
+    #[error("word list must not be empty")]
+    EmptyWordList,
+    #[error("at least {min} words are required, got {count}")]
+    TooFewWords { count: usize, min: usize },
+    #[error("at most {max} words are allowed, got {count}")]
+    TooManyWords { count: usize, max: usize },
+    #[error("word at index {index} is empty")]
+    EmptyWord { index: usize },
+    #[error("word '{word}' has {length} letters, but the grid only has room for {max}")]
+    WordTooLong {
+        word: String,
+        length: usize,
+        max: usize,
+    },
+    #[error("word '{word}' contains characters other than letters")]
+    InvalidCharacters { word: String },
+    #[error("word '{word}' is contained more than once")]
+    DuplicateWord { word: String },
+    #[error("word '{word}' contains the blocked word '{blocked}'")]
+    BlockedWord { word: String, blocked: String },
+    #[error("word '{word}' has no clue")]
+    MissingClue { word: String },
+    #[error("theme '{theme}' is not available in language '{language}'")]
+    UnknownTheme { theme: String, language: String },
+    #[error("either words or a theme can be given, not both")]
+    ThemeWithWords,
+    #[error("word count must be between {min} and {max}, got {count}")]
+    WordCountOutOfRange {
+        count: usize,
+        min: usize,
+        max: usize,
+    },
+    #[error("minimum word length {min_length} is greater than maximum word length {max_length}")]
+    InvalidWordLengthRange {
+        min_length: usize,
+        max_length: usize,
+    },
+    #[error("row {row} has {length} cells, but the grid is {width} cells wide")]
+    RaggedGrid {
+        row: usize,
+        length: usize,
+        width: usize,
+    },
+    #[error("word '{word}' is not at the given position in the grid")]
+    PlacementMismatch { word: String },
+    #[error("word '{word}' has no placement")]
+    MissingPlacement { word: String },
+    #[error("placement of '{word}' does not belong to a word of the list")]
+    UnlistedPlacement { word: String },
+    #[error("batch must contain between {min} and {max} puzzles, got {count}")]
+    BatchSizeOutOfRange {
+        count: usize,
+        min: usize,
+        max: usize,
+    },
+}
+
+/// Checks the generator options and returns *all* issues found.
+///
+/// Hosts should call this before [`crate::place_words`] and turn the
+/// errors into a `400 Bad Request` (see [`ProblemDetails`]).
+pub fn validate(options: &GeneratorOptions) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    let (width, height) = (options.width(), options.height());
+    let mut size_is_valid = true;
+    for (dimension, size) in [("width", width), ("height", height)] {
+        if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+            size_is_valid = false;
+            errors.push(ValidationError::SizeOutOfRange {
+                dimension,
+                size,
+                min: MIN_SIZE,
+                max: MAX_SIZE,
+            });
+        }
+    }
+
+    if let Some(mask) = &options.mask {
+        match mask.dimensions() {
+            Some((mask_width, mask_height)) if (mask_width, mask_height) != (width, height) => {
+                errors.push(ValidationError::MaskSizeMismatch {
+                    mask_width,
+                    mask_height,
+                    width,
+                    height,
+                });
+            }
+            _ if size_is_valid && mask.to_mask(width, height).usable_cells() == 0 => {
+                errors.push(ValidationError::EmptyMask);
+            }
+            _ => {}
+        }
+    }
+
+    match &options.theme {
+        None if options.words.is_empty() => errors.push(ValidationError::EmptyWordList),
+        None => {}
+        Some(_) if !options.words.is_empty() => errors.push(ValidationError::ThemeWithWords),
+        Some(theme) => {
+            if find_theme(theme, options.language()).is_none() {
+                errors.push(ValidationError::UnknownTheme {
+                    theme: theme.clone(),
+                    language: options.language().to_string(),
+                });
+            }
+
+            if let Some(count) = options.word_count {
+                if !(1..=MAX_THEME_WORD_COUNT).contains(&count) {
+                    errors.push(ValidationError::WordCountOutOfRange {
+                        count,
+                        min: 1,
+                        max: MAX_THEME_WORD_COUNT,
+                    });
+                }
+            }
+
+            let min_length = options.min_word_length.unwrap_or(DEFAULT_MIN_WORD_LENGTH);
+            if let Some(max_length) = options.max_word_length {
+                if min_length > max_length {
+                    errors.push(ValidationError::InvalidWordLengthRange {
+                        min_length,
+                        max_length,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (index, word) in options.words.iter().enumerate() {
+        if word.is_empty() {
+            errors.push(ValidationError::EmptyWord { index });
+            continue;
+        }
+
+        if !word.chars().all(is_letter) {
+            errors.push(ValidationError::InvalidCharacters { word: word.clone() });
+        }
+
+        // Only check length if size is valid, otherwise we would report every word
+        let length = word.chars().count();
+        let max = width.max(height);
+        if size_is_valid && length > max {
+            errors.push(ValidationError::WordTooLong {
+                word: word.clone(),
+                length,
+                max,
+            });
+        }
+
+        if !seen.insert(word.to_uppercase()) {
+            errors.push(ValidationError::DuplicateWord { word: word.clone() });
+        }
+
+        // Blocked words could never be removed from the grid if they are part of a word
+        let upper = word.to_uppercase();
+        let reversed: String = upper.chars().rev().collect();
+        for blocked in options.blocklist.iter().filter(|b| !b.is_empty()) {
+            let blocked_upper = blocked.to_uppercase();
+            if upper.contains(&blocked_upper) || reversed.contains(&blocked_upper) {
+                errors.push(ValidationError::BlockedWord {
+                    word: word.clone(),
+                    blocked: blocked.clone(),
+                });
+            }
+        }
+    }
+
+    for blocked in options.blocklist.iter() {
+        if blocked.is_empty() || !blocked.chars().all(is_letter) {
+            errors.push(ValidationError::InvalidCharacters {
+                word: blocked.clone(),
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Whether a character can be placed in a grid cell.
+///
+/// Words are placed in uppercase, so letters whose uppercase form has more
+/// than one character (e.g. 'ß' becoming "SS") would not fit into one cell.
+fn is_letter(c: char) -> bool {
+    c.is_alphabetic() && c.to_uppercase().count() == 1
+}
+
+/// Single entry in the `errors` list of [`ProblemDetails`]
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct ProblemIssue {
+    #[serde(flatten)]
+    pub error: ValidationError,
+    pub detail: String,
+}
+
+/// Error body following RFC 7807 (`application/problem+json`).
+///
+/// Both hosts use this so that clients get the same error format
+/// no matter where the generator runs.
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(schemars::JsonSchema))]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(test, schemars(default))]
+    pub errors: Vec<ProblemIssue>,
+}
+
+impl ProblemDetails {
+    /// Content type to use for responses containing problem details
+    pub const CONTENT_TYPE: &'static str = "application/problem+json";
+
+    /// Creates a problem with a free-text detail message
+    pub fn new<S: Into<String>>(status: u16, title: &str, detail: S) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: title.to_string(),
+            status,
+            detail: Some(detail.into()),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Creates a `400 Bad Request` problem with a free-text detail message
+    /// (e.g. for request bodies that cannot be parsed).
+    pub fn bad_request<S: Into<String>>(detail: S) -> Self {
+        Self::new(400, "Bad Request", detail)
+    }
+}
+
+impl From<GeneratorError> for ProblemDetails {
+    fn from(error: GeneratorError) -> Self {
+        if let GeneratorError::InvalidOptions(error) = error {
+            return vec![error].into();
+        }
+
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: "Puzzle could not be generated".to_string(),
+            status: 400,
+            detail: Some(error.to_string()),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl From<Vec<ValidationError>> for ProblemDetails {
+    fn from(errors: Vec<ValidationError>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: "Invalid generator options".to_string(),
+            status: 400,
+            detail: None,
+            errors: errors
+                .into_iter()
+                .map(|error| ProblemIssue {
+                    detail: error.to_string(),
+                    error,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MaskSpec, DEFAULT_LANGUAGE};
+
+    fn options(size: usize, words: &[&str]) -> GeneratorOptions {
+        GeneratorOptions {
+            size,
+            words: words.iter().map(|w| w.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_options() {
+        assert!(validate(&options(10, &["CLOUD", "RUST"])).is_ok());
+    }
+
+    #[test]
+    fn reports_every_issue() {
+        let errors = validate(&options(4, &["CLOUDS", "", "AB1", "ab", "AB"])).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::WordTooLong {
+                    word: "CLOUDS".to_string(),
+                    length: 6,
+                    max: 4
+                },
+                ValidationError::EmptyWord { index: 1 },
+                ValidationError::InvalidCharacters {
+                    word: "AB1".to_string()
+                },
+                ValidationError::DuplicateWord {
+                    word: "AB".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn letters_need_a_single_uppercase_form() {
+        assert!(validate(&options(10, &["rust", "Übung"])).is_ok());
+        assert_eq!(
+            validate(&options(10, &["RUST", "straße"])).unwrap_err(),
+            vec![ValidationError::InvalidCharacters {
+                word: "straße".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn size_and_empty_list() {
+        let errors = validate(&options(21, &[])).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::SizeOutOfRange {
+                    dimension: "width",
+                    size: 21,
+                    min: MIN_SIZE,
+                    max: MAX_SIZE
+                },
+                ValidationError::SizeOutOfRange {
+                    dimension: "height",
+                    size: 21,
+                    min: MIN_SIZE,
+                    max: MAX_SIZE
+                },
+                ValidationError::EmptyWordList,
+            ]
+        );
+    }
+
+    #[test]
+    fn theme() {
+        let mut themed = options(10, &[]);
+        themed.theme = Some("animals".to_string());
+        assert!(validate(&themed).is_ok());
+
+        themed.language = Some("xx".to_string());
+        themed.word_count = Some(0);
+        themed.min_word_length = Some(8);
+        themed.max_word_length = Some(4);
+        assert_eq!(
+            validate(&themed).unwrap_err(),
+            vec![
+                ValidationError::UnknownTheme {
+                    theme: "animals".to_string(),
+                    language: "xx".to_string()
+                },
+                ValidationError::WordCountOutOfRange {
+                    count: 0,
+                    min: 1,
+                    max: MAX_THEME_WORD_COUNT
+                },
+                ValidationError::InvalidWordLengthRange {
+                    min_length: 8,
+                    max_length: 4
+                },
+            ]
+        );
+
+        let mut both = options(10, &["CLOUD"]);
+        both.theme = Some("animals".to_string());
+        assert_eq!(
+            validate(&both).unwrap_err(),
+            vec![ValidationError::ThemeWithWords]
+        );
+    }
+
+    #[test]
+    fn blocklist() {
+        let mut blocked = options(10, &["CLOUD", "SNAKE"]);
+        blocked.blocklist = vec!["EKA".to_string(), "A-B".to_string()];
+        assert_eq!(
+            validate(&blocked).unwrap_err(),
+            vec![
+                ValidationError::BlockedWord {
+                    word: "SNAKE".to_string(),
+                    blocked: "EKA".to_string()
+                },
+                ValidationError::InvalidCharacters {
+                    word: "A-B".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rectangular_grid_and_mask() {
+        let mut rectangular = options(0, &["SERVERLESS"]);
+        rectangular.width = Some(10);
+        rectangular.height = Some(4);
+        assert!(validate(&rectangular).is_ok());
+
+        let mut masked = options(0, &["RUST"]);
+        masked.width = Some(5);
+        masked.mask = Some(MaskSpec::Ascii(vec![
+            "....".to_string(),
+            "....".to_string(),
+        ]));
+        assert_eq!(
+            validate(&masked).unwrap_err(),
+            vec![ValidationError::MaskSizeMismatch {
+                mask_width: 4,
+                mask_height: 2,
+                width: 5,
+                height: 2
+            }]
+        );
+
+        masked.width = None;
+        assert_eq!(
+            validate(&masked).unwrap_err(),
+            vec![ValidationError::EmptyMask]
+        );
+    }
+
+    #[test]
+    fn generator_reports_unknown_theme_as_validation_error() {
+        let mut themed = options(10, &[]);
+        themed.theme = Some("dragons".to_string());
+        let error = crate::place_words(themed).unwrap_err();
+        assert_eq!(
+            error,
+            GeneratorError::InvalidOptions(ValidationError::UnknownTheme {
+                theme: "dragons".to_string(),
+                language: DEFAULT_LANGUAGE.to_string()
+            })
+        );
+
+        let json = serde_json::to_value(ProblemDetails::from(error)).unwrap();
+        assert_eq!(json["status"], 400);
+        assert_eq!(json["errors"][0]["code"], "unknownTheme");
+    }
+
+    #[test]
+    fn problem_json() {
+        let problem: ProblemDetails = vec![ValidationError::EmptyWordList].into();
+        let json = serde_json::to_value(&problem).unwrap();
+        assert_eq!(json["status"], 400);
+        assert_eq!(json["errors"][0]["code"], "emptyWordList");
+        assert_eq!(json["errors"][0]["detail"], "word list must not be empty");
+    }
+}
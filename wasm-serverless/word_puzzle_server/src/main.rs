@@ -0,0 +1,244 @@
+use std::{
+    convert::Infallible,
+    env,
+    net::SocketAddr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, Method as HttpMethod, Uri},
+    response::Response,
+    Router,
+};
+use futures_util::stream;
+use tokio::{sync::mpsc, task::JoinError};
+use word_puzzle_generator::{
+    api::{
+        client_key, handle_rate_limited, ApiKeys, ApiRequest, ApiResponse, Host, MemoryPuzzleStore,
+        MemoryStore, Method, RateLimiter,
+    },
+    ProblemDetails,
+};
+
+/// Port used if `PORT` is not set
+const DEFAULT_PORT: u16 = 3000;
+
+/// Number of streamed chunks (e.g. batch lines) generated ahead of the client
+const STREAM_BUFFER: usize = 16;
+
+/// Rate limit buckets and shared puzzles are kept in memory, they are lost on restart
+#[derive(Default)]
+struct AppState {
+    limiter: RateLimiter,
+    /// Keys from `API_KEYS` (comma-separated), clients sending one of them in
+    /// `X-Api-Key` get their own bucket
+    api_keys: ApiKeys,
+    buckets: MemoryStore,
+    puzzles: MemoryPuzzleStore,
+}
+
+/// Native host for local development, same contract as the Spin and
+/// Cloudflare hosts (see [`word_puzzle_generator::api`]).
+#[tokio::main]
+async fn main() -> Result<()> {
+    let port = env::var("PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_PORT);
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    println!("Listening on http://{address}");
+    let api_keys = ApiKeys::parse(&env::var("API_KEYS").unwrap_or_default());
+    let app = app(RateLimiter::default(), api_keys);
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
+    Ok(())
+}
+
+fn app(limiter: RateLimiter, api_keys: ApiKeys) -> Router {
+    let state = Arc::new(AppState {
+        limiter,
+        api_keys,
+        ..Default::default()
+    });
+
+    // Routing is done by the shared core, so every request goes to the same handler
+    Router::new().fallback(handle_request).with_state(state)
+}
+
+async fn handle_request(
+    State(state): State<Arc<AppState>>,
+    address: Option<ConnectInfo<SocketAddr>>,
+    method: HttpMethod,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request = ApiRequest {
+        method: Method::parse(method.as_str()),
+        path: uri.path().to_string(),
+        query: uri.query().unwrap_or_default().to_string(),
+        accept: headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string(),
+        body: body.to_vec(),
+    };
+
+    let api_key = headers.get("X-Api-Key").and_then(|v| v.to_str().ok());
+    let address = address.map(|ConnectInfo(a)| a.ip().to_string());
+    let client = client_key(api_key, address.as_deref(), &state.api_keys);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+
+    // Generating puzzles is CPU-bound, keep it off the async workers
+    let response = tokio::task::spawn_blocking(move || {
+        let AppState {
+            limiter,
+            buckets,
+            puzzles,
+            ..
+        } = state.as_ref();
+        let host = Host::new("Hello from axum!", now).with_puzzles(puzzles);
+        handle_rate_limited(&request, &host, limiter, buckets, &client)
+    })
+    .await
+    .unwrap_or_else(handler_failed);
+    into_response(response)
+}
+
+/// Responds with `500 Internal Server Error` if the handler panicked (or was
+/// cancelled), instead of taking down the connection.
+fn handler_failed(error: JoinError) -> ApiResponse {
+    eprintln!("Request handler failed: {error}");
+    ApiResponse::problem(ProblemDetails::new(
+        500,
+        "Internal Server Error",
+        "the request could not be handled",
+    ))
+}
+
+fn into_response(response: ApiResponse) -> Response {
+    let mut builder = Response::builder().status(response.status);
+    for (name, value) in response.headers.iter() {
+        builder = builder.header(name, value);
+    }
+
+    let body = match response.stream {
+        None => Body::from(response.body),
+        Some(stream) => {
+            // Chunks are generated on a blocking thread and sent as soon as they
+            // are available; generation stops when the client disconnects
+            let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+            tokio::task::spawn_blocking(move || {
+                for chunk in std::iter::once(response.body).chain(stream) {
+                    if sender.blocking_send(chunk).is_err() {
+                        break;
+                    }
+                }
+            });
+            Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+                let chunk = receiver.recv().await?;
+                Some((Ok::<_, Infallible>(chunk), receiver))
+            }))
+        }
+    };
+
+    builder
+        .body(body)
+        .expect("status and headers from the core are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, http::Request};
+    use tower::ServiceExt;
+    use word_puzzle_generator::api::{conformance, RateLimitPolicy};
+
+    use super::*;
+
+    #[test]
+    fn server_passes_conformance_suite() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let app = app(RateLimiter::default(), ApiKeys::default());
+        conformance::run(|request| {
+            runtime.block_on(async {
+                let method = match request.method {
+                    Method::Get => HttpMethod::GET,
+                    Method::Post => HttpMethod::POST,
+                    Method::Options => HttpMethod::OPTIONS,
+                    Method::Other => HttpMethod::PUT,
+                };
+                let http_request = Request::builder()
+                    .method(method)
+                    .uri(format!("{}?{}", request.path, request.query))
+                    .header(header::ACCEPT, request.accept)
+                    .body(Body::from(request.body))
+                    .unwrap();
+
+                let response = app.clone().oneshot(http_request).await.unwrap();
+                let status = response.status().as_u16();
+                let headers = response
+                    .headers()
+                    .iter()
+                    .map(|(n, v)| (n.to_string(), v.to_str().unwrap().to_string()))
+                    .collect();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                ApiResponse {
+                    status,
+                    headers,
+                    body: body.to_vec(),
+                    stream: None,
+                }
+            })
+        });
+    }
+
+    #[test]
+    fn panics_become_problems() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let task = runtime.spawn_blocking(|| panic!("generator bug"));
+        let error = runtime.block_on(task).unwrap_err();
+        let response = handler_failed(error);
+        assert_eq!(response.status, 500);
+        assert_eq!(
+            response.header("Content-Type"),
+            Some(ProblemDetails::CONTENT_TYPE)
+        );
+    }
+
+    #[test]
+    fn rate_limit() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let app = app(
+            RateLimiter::new(RateLimitPolicy {
+                capacity: 1f64,
+                refill_per_second: 0.01f64,
+            }),
+            ApiKeys::default(),
+        );
+        let statuses: Vec<u16> = (0..2)
+            .map(|_| {
+                let request = Request::builder()
+                    .method(HttpMethod::POST)
+                    .uri("/generate")
+                    .body(Body::from(r#"{"size": 6, "words": ["RUST"]}"#))
+                    .unwrap();
+                runtime.block_on(app.clone().oneshot(request)).unwrap()
+            })
+            .map(|response| response.status().as_u16())
+            .collect();
+        assert_eq!(statuses, vec![200, 429]);
+    }
+}
@@ -1,64 +1,147 @@
+use std::{
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use anyhow::Result;
-use http::response::Builder;
 use spin_sdk::{
-    http::{Request, Response, Params},
-    http_component, http_router,
+    config,
+    http::{Request, Response},
+    http_component,
+    key_value::Store,
 };
-use word_puzzle_generator::{place_words, GeneratorOptions};
+use word_puzzle_generator::api::{
+    client_key, handle_rate_limited, ApiKeys, ApiRequest, ApiResponse, Host, Method, PuzzleStore,
+    RateLimitStore, RateLimiter, StoreError, StoredPuzzle, TokenBucket,
+};
+
+/// Prefix of shared puzzle keys, rate limit buckets use the client key
+const PUZZLE_KEY_PREFIX: &str = "puzzle:";
 
+/// Spin's store has no expiry, about one request in this many (by the
+/// milliseconds of its time) removes expired entries
+const CLEANUP_INTERVAL: u64 = 100;
+
+/// Thin adapter, routing and error handling live in [`word_puzzle_generator::api`]
 #[http_component]
 fn handle_word_puzzle_spin(req: Request) -> Result<Response> {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    let request = ApiRequest {
+        method: Method::parse(req.method().as_str()),
+        path: req.uri().path().to_string(),
+        query: req.uri().query().unwrap_or_default().to_string(),
+        accept: header("Accept").unwrap_or_default().to_string(),
+        body: req
+            .body()
+            .as_ref()
+            .map(|b| b.to_vec())
+            .unwrap_or_default(),
+    };
+
+    // Spin passes the client address including the port
+    let address = header("spin-client-addr").map(|a| {
+        a.parse::<SocketAddr>()
+            .map(|a| a.ip().to_string())
+            .unwrap_or_else(|_| a.to_string())
+    });
+    // Comma-separated keys from the `api_keys` variable (see `spin.toml`)
+    let api_keys = ApiKeys::parse(&config::get("api_keys").unwrap_or_default());
+    let client = client_key(header("X-Api-Key"), address.as_deref(), &api_keys);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
 
-		if req.method() == http::Method::OPTIONS {
-				return Ok(http::Response::builder()
-						.status(http::StatusCode::OK)
-						.header("Access-Control-Allow-Origin", "*")
-						.header("Access-Control-Allow-Methods", "*")
-						.header("Access-Control-Allow-Headers", "*")
-						.body(None)?);
-		}
+    let limiter = RateLimiter::default();
+    let store = KeyValueStore(Store::open_default().ok());
+    if now % CLEANUP_INTERVAL == 0 {
+        store.remove_expired(now, limiter.ttl_seconds());
+    }
 
-    let router = http_router! {
-    	GET "/" => |_req, _params| {
-        Ok(http::Response::builder()
-            .status(http::StatusCode::OK)
-            .body(Some("Hello from spin!".into()))?)
-    	},
+    let mut host = Host::new("Hello from spin!", now);
+    if store.0.is_some() {
+        host = host.with_puzzles(&store);
+    }
+    into_response(handle_rate_limited(
+        &request,
+        &host,
+        &limiter,
+        &store,
+        &client,
+    ))
+}
 
-			POST "/generate" => generate_puzzle
-		};
+/// Spin 1.x components cannot stream response bodies, streamed responses
+/// (e.g. batches) are sent once they are complete
+fn into_response(response: ApiResponse) -> Result<Response> {
+    let response = response.collect_stream();
+    let mut builder = http::Response::builder().status(response.status);
+    for (name, value) in response.headers.iter() {
+        builder = builder.header(name, value);
+    }
 
-    router.handle(req)
+    let body = (!response.body.is_empty()).then(|| response.body.into());
+    Ok(builder.body(body)?)
 }
 
-fn generate_puzzle(req: Request, _params: Params) -> Result<Response> {
-    let body = req.body().as_ref().unwrap();
-    let options: GeneratorOptions = serde_json::from_str(std::str::from_utf8(body.as_ref())?)?;
+/// Rate limit buckets and shared puzzles in Spin's default key-value store.
+/// If the store cannot be opened, requests are not limited and puzzles cannot
+/// be shared. Spin's store has no expiry, expired entries are removed by
+/// [`KeyValueStore::remove_expired`] and ignored until then.
+struct KeyValueStore(Option<Store>);
 
-    if options.size > 20 {
-        return Ok(http::Response::builder()
-            .status(http::StatusCode::BAD_REQUEST)
-            .body(None)?);
+impl KeyValueStore {
+    /// Removes expired puzzles and rate limit buckets that are full again (a
+    /// missing bucket is the same as a full one)
+    fn remove_expired(&self, now: u64, bucket_ttl_seconds: u64) {
+        let Some(store) = &self.0 else {
+            return;
+        };
+        for key in store.get_keys().unwrap_or_default() {
+            let expired = match key.strip_prefix(PUZZLE_KEY_PREFIX) {
+                Some(id) => PuzzleStore::load(self, id).is_some_and(|p| p.expires_at <= now),
+                None => RateLimitStore::load(self, &key)
+                    .is_some_and(|bucket| bucket.updated_at + bucket_ttl_seconds * 1000 <= now),
+            };
+            if expired {
+                let _ = store.delete(&key);
+            }
+        }
     }
+}
 
-    let puzzle = place_words(options);
+impl RateLimitStore for KeyValueStore {
+    fn load(&self, key: &str) -> Option<TokenBucket> {
+        let bytes = self.0.as_ref()?.get(key).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
 
-    let response = serde_json::to_string_pretty(&puzzle)?.as_bytes().to_vec();
-    Ok(http::Response::builder()
-        .status(http::StatusCode::OK)
-				.with_cors()
-        .header("Content-Type", "application/json")
-				.body(Some(response.into()))?)
+    /// Expired buckets are removed by [`KeyValueStore::remove_expired`]
+    fn save(&self, key: &str, bucket: &TokenBucket, _ttl_seconds: u64) {
+        if let (Some(store), Ok(bytes)) = (&self.0, serde_json::to_vec(bucket)) {
+            let _ = store.set(key, bytes);
+        }
+    }
 }
 
-trait WithCors {
-	fn with_cors(self) -> Self;
-}
+impl PuzzleStore for KeyValueStore {
+    fn load(&self, id: &str) -> Option<StoredPuzzle> {
+        let bytes = self
+            .0
+            .as_ref()?
+            .get(format!("{PUZZLE_KEY_PREFIX}{id}"))
+            .ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
 
-impl WithCors for Builder {
-	fn with_cors(self) -> Self {
-		self.header("Access-Control-Allow-Origin", "*")
-				.header("Access-Control-Allow-Methods", "*")
-				.header("Access-Control-Allow-Headers", "*")
-	}
+    fn save(&self, id: &str, puzzle: &StoredPuzzle) -> Result<(), StoreError> {
+        let store = self
+            .0
+            .as_ref()
+            .ok_or_else(|| StoreError("key-value store is not available".to_string()))?;
+        let bytes = serde_json::to_vec(puzzle).map_err(|e| StoreError(e.to_string()))?;
+        store
+            .set(format!("{PUZZLE_KEY_PREFIX}{id}"), bytes)
+            .map_err(|e| StoreError(format!("{e:?}")))
+    }
 }